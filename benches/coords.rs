@@ -0,0 +1,41 @@
+//! Throughput of `boxarray_` on a 96^3 grid, comparing the incremental coordinates it uses with coordinates computed from
+//! the flat index with a division and a modulo per array nesting, as a plain index loop.
+//!
+//! Run with `cargo bench --bench coords`.
+use boxarray::Shape;
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+type Grid = [[[u32; 96]; 96]; 96];
+
+const RUNS: u32 = 10;
+
+fn bench<A>(name: &str, f: impl Fn() -> Box<A>) {
+    let mut best = Duration::MAX;
+    for _ in 0..RUNS {
+        let start = Instant::now();
+        let a = black_box(f());
+        best = best.min(start.elapsed());
+        drop(a);
+    }
+    let cells = std::mem::size_of::<A>() as f64 / std::mem::size_of::<u32>() as f64;
+    println!(
+        "{name:<24} {:>10.3} ms {:>8.2} Gcells/s",
+        best.as_secs_f64() * 1e3,
+        cells / best.as_secs_f64() / 1e9
+    );
+}
+
+fn main() {
+    let f = |((((), i), j), k): ((((), usize), usize), usize)| (i ^ j ^ k) as u32;
+    bench("boxarray_ (incremental)", || {
+        boxarray::boxarray_::<u32, _, Grid, _>(black_box(f))
+    });
+    bench("div/mod per cell", || {
+        let cells = (0..<Grid as Shape<u32>>::LEN).map(|n| {
+            let n = black_box(n);
+            f(((((), n % 96), n / 96 % 96), n / (96 * 96)))
+        });
+        boxarray::boxarray_from_iter::<u32, _, Grid, _>(cells).unwrap()
+    });
+}
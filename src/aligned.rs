@@ -0,0 +1,154 @@
+//! Heap allocations aligned beyond the alignment of their type.
+use crate::init;
+use crate::private::*;
+use std::alloc::{dealloc, Layout};
+use std::fmt;
+use std::marker::PhantomData;
+use std::ops::{Deref, DerefMut};
+use std::ptr::{self, NonNull};
+
+/// Owned heap allocation of an `A` aligned to at least `ALIGN` bytes, such as 64 for cache lines and AVX-512 or 4096 for
+/// pages.
+///
+/// It dereferences to `A` and frees the memory with the same layout it was allocated with. `ALIGN` must be a power of
+/// two, which is checked at compile time. When `A` itself requires a larger alignment, that one is used.
+///
+/// # Examples
+///
+/// ```
+/// use boxarray::AlignedBox;
+///
+/// let a: AlignedBox<[[f32; 1024]; 1024], 4096> = boxarray::boxarray_aligned(1.0);
+/// assert_eq!(a.as_ptr() as usize % 4096, 0);
+/// assert_eq!(a[1023][1023], 1.0);
+/// ```
+///
+/// Fails to compile when `ALIGN` is not a power of two.
+/// ```compile_fail
+/// let a: boxarray::AlignedBox<[[f32; 16]; 16], 48> = boxarray::boxarray_aligned(1.0);
+/// ```
+pub struct AlignedBox<A, const ALIGN: usize> {
+    ptr: NonNull<A>,
+    _owned: PhantomData<A>,
+}
+
+unsafe impl<A: Send, const ALIGN: usize> Send for AlignedBox<A, ALIGN> {}
+unsafe impl<A: Sync, const ALIGN: usize> Sync for AlignedBox<A, ALIGN> {}
+
+impl<A, const ALIGN: usize> AlignedBox<A, ALIGN> {
+    /// Layout of the allocation.
+    pub fn layout() -> Layout {
+        const { assert!(ALIGN.is_power_of_two(), "ALIGN must be a power of two") };
+        Layout::new::<A>()
+            .align_to(ALIGN)
+            .expect("aligned size overflows isize")
+    }
+
+    /// Pointer to the start of the allocation, aligned to `ALIGN`.
+    pub fn as_ptr(&self) -> *const A {
+        self.ptr.as_ptr()
+    }
+
+    /// Mutable pointer to the start of the allocation, aligned to `ALIGN`.
+    pub fn as_mut_ptr(&mut self) -> *mut A {
+        self.ptr.as_ptr()
+    }
+
+    /// Allocate an `A` made of `n` values of type `E` initialized by `f` through the initialization core.
+    ///
+    /// # Safety
+    ///
+    /// `A` must consist of exactly `n` consecutive values of type `E`.
+    unsafe fn init<E>(n: usize, f: impl FnMut(usize) -> E) -> Self {
+        let ptr = init::init_raw::<E, A>(Self::layout(), n, f);
+        AlignedBox {
+            ptr: NonNull::new_unchecked(ptr),
+            _owned: PhantomData,
+        }
+    }
+}
+
+impl<A, const ALIGN: usize> Drop for AlignedBox<A, ALIGN> {
+    fn drop(&mut self) {
+        let layout = Self::layout();
+        unsafe {
+            ptr::drop_in_place(self.ptr.as_ptr());
+            if layout.size() != 0 {
+                dealloc(self.ptr.as_ptr() as *mut u8, layout);
+            }
+        }
+    }
+}
+
+impl<A, const ALIGN: usize> Deref for AlignedBox<A, ALIGN> {
+    type Target = A;
+
+    fn deref(&self) -> &A {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<A, const ALIGN: usize> DerefMut for AlignedBox<A, ALIGN> {
+    fn deref_mut(&mut self) -> &mut A {
+        unsafe { self.ptr.as_mut() }
+    }
+}
+
+impl<A: fmt::Debug, const ALIGN: usize> fmt::Debug for AlignedBox<A, ALIGN> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        (**self).fmt(f)
+    }
+}
+
+/// Same as `boxarray` but return an `AlignedBox` aligned to at least `ALIGN` bytes.
+///
+/// # Examples
+///
+/// ```
+/// use boxarray::AlignedBox;
+///
+/// let a: AlignedBox<[[f32; 16]; 16], 64> = boxarray::boxarray_aligned(0.5);
+/// assert_eq!(a.as_ptr() as usize % 64, 0);
+/// assert_eq!(*a, [[0.5; 16]; 16]);
+///
+/// let a: AlignedBox<(), 4096> = boxarray::boxarray_aligned(());
+/// assert_eq!(a.as_ptr() as usize % 4096, 0);
+/// ```
+pub fn boxarray_aligned<
+    const ALIGN: usize,
+    E: Clone,
+    L: CUList + Product<usize>,
+    A: Arrays<E, L>,
+>(
+    e: E,
+) -> AlignedBox<A, ALIGN> {
+    unsafe { AlignedBox::init(L::product(), |_| e.clone()) }
+}
+
+/// Same as `boxarray_` but return an `AlignedBox` aligned to at least `ALIGN` bytes.
+///
+/// If the function panics, the cells already initialized are dropped and the allocation is freed with the aligned layout.
+///
+/// # Examples
+///
+/// ```
+/// use boxarray::AlignedBox;
+///
+/// let a: AlignedBox<[[u64; 100]; 100], 64> = boxarray::boxarray_aligned_(|(((), i), j)| (i + 100 * j) as u64);
+/// assert_eq!(a.as_ptr() as usize % 64, 0);
+/// assert_eq!(a[99][99], 9999);
+///
+/// let b: Box<[[u64; 100]; 100]> = boxarray::boxarray_(|(((), i), j)| (i + 100 * j) as u64);
+/// assert_eq!(*a, *b);
+/// ```
+pub fn boxarray_aligned_<
+    const ALIGN: usize,
+    E,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: Fn(CoordType<L>) -> E,
+>(
+    f: F,
+) -> AlignedBox<A, ALIGN> {
+    unsafe { AlignedBox::init(L::product(), in_order::<E, L>(0, f)) }
+}
@@ -0,0 +1,105 @@
+//! Initialization inside the memory of an allocator other than the global one.
+//!
+//! The `Allocator` trait and the `Box` supporting it come from the standard library with the `allocator_api` feature, which
+//! requires a nightly compiler, or from the `allocator-api2` crate on stable.
+use crate::init;
+use crate::private::*;
+#[cfg(not(feature = "allocator_api"))]
+use allocator_api2::{alloc::Allocator, boxed::Box};
+#[cfg(feature = "allocator_api")]
+use std::{alloc::Allocator, boxed::Box};
+
+/// Initialize `n` values of type `E` calling `f` with their index, inside an `A` allocated by `alloc`.
+///
+/// # Safety
+///
+/// `A` must be made of exactly `n` consecutive values of type `E`.
+unsafe fn init_in<E, A, Alloc: Allocator>(
+    alloc: Alloc,
+    n: usize,
+    f: impl FnMut(usize) -> E,
+) -> Box<A, Alloc> {
+    let mut uninit = Box::<A, Alloc>::new_uninit_in(alloc);
+    init::fill(uninit.as_mut_ptr() as *mut E, n, f);
+    uninit.assume_init()
+}
+
+/// Same as `boxarray` but allocate the nested arrays with `alloc` instead of the global allocator.
+///
+/// The allocator is a `std::alloc::Allocator` with the `allocator_api` feature on nightly, or an
+/// `allocator_api2::alloc::Allocator` with the `allocator-api2` feature on stable. If cloning panics, the cells already
+/// initialized are dropped and the memory is given back to `alloc` before the panic is propagated.
+///
+/// # Examples
+///
+/// ```
+/// # #![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+/// # #[cfg(feature = "allocator_api")]
+/// # use std::alloc::Global;
+/// # #[cfg(not(feature = "allocator_api"))]
+/// # use boxarray::allocator_api2::alloc::Global;
+/// let a = boxarray::boxarray_in::<_, _, [[[f64; 3]; 2]; 4], _>(7.0, Global);
+/// assert_eq!(*a, [[[7.0; 3]; 2]; 4]);
+/// ```
+pub fn boxarray_in<E: Clone, L: CUList + Product<usize>, A: Arrays<E, L>, Alloc: Allocator>(
+    e: E,
+    alloc: Alloc,
+) -> Box<A, Alloc> {
+    unsafe { init_in(alloc, L::product(), |_| e.clone()) }
+}
+
+/// Same as `boxarray_` but allocate the nested arrays with `alloc` instead of the global allocator, see `boxarray_in`.
+///
+/// # Examples
+///
+/// An allocator counting the bytes it currently holds.
+/// ```
+/// # #![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+/// # #[cfg(feature = "allocator_api")]
+/// # use std::alloc::{AllocError, Allocator, Global};
+/// # #[cfg(not(feature = "allocator_api"))]
+/// # use boxarray::allocator_api2::alloc::{AllocError, Allocator, Global};
+/// use std::alloc::Layout;
+/// use std::ptr::NonNull;
+/// use std::sync::atomic::{AtomicUsize, Ordering};
+///
+/// struct Counting<'a>(&'a AtomicUsize);
+/// unsafe impl Allocator for Counting<'_> {
+///     fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+///         self.0.fetch_add(layout.size(), Ordering::Relaxed);
+///         Global.allocate(layout)
+///     }
+///     unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+///         self.0.fetch_sub(layout.size(), Ordering::Relaxed);
+///         Global.deallocate(ptr, layout)
+///     }
+/// }
+///
+/// let held = AtomicUsize::new(0);
+/// let a = boxarray::boxarray_in_::<u32, _, [[u32; 100]; 10], _, _>(|(((), i), j)| (i + 100 * j) as u32, Counting(&held));
+/// assert_eq!(a[9][99], 999);
+/// assert_eq!(held.load(Ordering::Relaxed), 4000);
+/// drop(a);
+/// assert_eq!(held.load(Ordering::Relaxed), 0);
+///
+/// let res = std::panic::catch_unwind(|| {
+///     let _ = boxarray::boxarray_in_::<String, _, [String; 10], _, _>(
+///         |((), i)| if i < 5 { i.to_string() } else { panic!("cell {i}") },
+///         Counting(&held),
+///     );
+/// });
+/// assert!(res.is_err());
+/// assert_eq!(held.load(Ordering::Relaxed), 0);
+/// ```
+pub fn boxarray_in_<
+    E,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: Fn(CoordType<L>) -> E,
+    Alloc: Allocator,
+>(
+    f: F,
+    alloc: Alloc,
+) -> Box<A, Alloc> {
+    unsafe { init_in(alloc, L::product(), in_order::<E, L>(0, f)) }
+}
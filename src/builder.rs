@@ -0,0 +1,331 @@
+//! Initialization of the cells in any order, either unchecked or tracked by a `Builder`.
+use crate::init::RawBox;
+use crate::private::*;
+use std::fmt;
+use std::marker::PhantomData;
+use std::mem::MaybeUninit;
+
+/// Allocate uninitialized nested arrays on the heap, to be initialized in place by `unsafe` code.
+///
+/// Use `Builder` to initialize the cells in any order without `unsafe`.
+///
+/// # Examples
+///
+/// ```
+/// let mut a = boxarray::boxarray_uninit::<[[u32; 3]; 2]>();
+/// let flat = a.as_mut_ptr() as *mut u32;
+/// for i in (0..6).rev() {
+///     unsafe { flat.add(i).write(i as u32) };
+/// }
+/// let a: Box<[[u32; 3]; 2]> = unsafe { a.assume_init() };
+/// assert_eq!(*a, [[0, 1, 2], [3, 4, 5]]);
+/// ```
+pub fn boxarray_uninit<A>() -> Box<MaybeUninit<A>> {
+    Box::new_uninit()
+}
+
+/// Nested arrays being initialized in any order, such as tiles read out of order from a file.
+///
+/// The cells are of type `E`, which is given first as for the `boxarray` functions, and the other parameters are usually
+/// inferred. A bitset records which cells have been written, so that `finish` only gives the `Box<A>` once every cell is set and
+/// otherwise gives the builder back to list the missing ones. When a `Builder` is dropped without being finished, the cells
+/// already written are dropped and the allocation is freed.
+///
+/// # Examples
+///
+/// ```
+/// use boxarray::Builder;
+///
+/// let mut b = Builder::<u32, _, [[u32; 4]; 4]>::new();
+/// for tile in [3, 1, 2, 0] {
+///     let (ti, tj) = (2 * (tile % 2), 2 * (tile / 2));
+///     for j in tj..tj + 2 {
+///         for i in ti..ti + 2 {
+///             b.set((((), i), j), (i + 4 * j) as u32);
+///         }
+///     }
+/// }
+/// let a = b.finish().unwrap();
+/// assert_eq!(a[3], [12, 13, 14, 15]);
+/// ```
+///
+/// Finishing too early gives the builder back, which lists the missing coordinates.
+/// ```
+/// use boxarray::Builder;
+///
+/// let mut b = Builder::<String, _, [[String; 2]; 2]>::new();
+/// b.set_idx([0, 0], "a".to_string());
+/// b.set_idx([1, 1], "d".to_string());
+/// let err = b.finish().unwrap_err();
+/// assert_eq!(err.to_string(), "2 cells are not initialized");
+/// assert!(err.missing().eq([(((), 1), 0), (((), 0), 1)]));
+///
+/// let mut b = err.into_builder();
+/// b.set_idx([1, 0], "b".to_string());
+/// b.set_idx([0, 1], "c".to_string());
+/// assert_eq!(*b.finish().unwrap(), [["a", "b"], ["c", "d"]]);
+/// ```
+///
+/// A builder can be moved to another thread when its cells can.
+/// ```
+/// let mut b = boxarray::Builder::<u64, _, [[u64; 3]; 3]>::new();
+/// b.set_idx([2, 2], 8);
+/// let b = std::thread::spawn(move || {
+///     for i in 0..8 {
+///         b.set_index(i, i as u64);
+///     }
+///     b
+/// })
+/// .join()
+/// .unwrap();
+/// assert_eq!(b.get_idx([1, 2]), Some(&7));
+/// assert_eq!(*b.finish().unwrap(), [[0, 1, 2], [3, 4, 5], [6, 7, 8]]);
+/// ```
+///
+/// Any element type can be used, including arrays taken as a whole.
+/// ```
+/// use std::num::FpCategory;
+///
+/// let mut b = boxarray::Builder::<FpCategory, _, [[FpCategory; 2]; 2]>::new();
+/// for i in [3, 0, 2, 1] {
+///     b.set_index(i, FpCategory::Normal);
+/// }
+/// assert_eq!(*b.finish().unwrap(), [[FpCategory::Normal; 2]; 2]);
+///
+/// let mut b = boxarray::Builder::<[u8; 3], _, [[[u8; 3]; 2]; 2]>::new();
+/// b.set_idx([1, 0], [1, 2, 3]);
+/// assert_eq!(b.get((((), 1), 0)), Some(&[1, 2, 3]));
+/// assert_eq!(b.missing_count(), 3);
+/// ```
+///
+/// The cells already written are dropped with an unfinished builder.
+/// ```
+/// use std::rc::Rc;
+///
+/// let cell = Rc::new(());
+/// let mut b = boxarray::Builder::<Rc<()>, _, [[Rc<()>; 8]; 8]>::new();
+/// b.set_idx([3, 5], Rc::clone(&cell));
+/// b.set_idx([3, 5], Rc::clone(&cell));
+/// b.set_idx([7, 7], Rc::clone(&cell));
+/// assert_eq!(Rc::strong_count(&cell), 3);
+/// drop(b);
+/// assert_eq!(Rc::strong_count(&cell), 1);
+/// ```
+pub struct Builder<E, L: CUList, A: Arrays<E, L>> {
+    raw: Option<RawBox<A>>,
+    written: Vec<u64>,
+    count: usize,
+    _cells: PhantomData<(E, L)>,
+}
+
+// The builder owns the cells written so far, like a `Box<A>`.
+unsafe impl<E: Send, L: CUList, A: Arrays<E, L>> Send for Builder<E, L, A> {}
+unsafe impl<E: Sync, L: CUList, A: Arrays<E, L>> Sync for Builder<E, L, A> {}
+
+impl<E, L: CUList, A: Arrays<E, L>> Builder<E, L, A> {
+    fn cells(&self) -> *mut E {
+        self.raw.as_ref().expect("builder is live").as_mut_ptr() as *mut E
+    }
+
+    fn is_written(&self, i: usize) -> bool {
+        self.written[i / 64] & (1 << (i % 64)) != 0
+    }
+}
+
+impl<E, L: CUList + IndexCoord<L>, A: Arrays<E, L>> Builder<E, L, A> {
+    /// Allocate the nested arrays with no cell written, calling `handle_alloc_error` if the allocation fails.
+    pub fn new() -> Self {
+        Builder {
+            raw: Some(RawBox::new()),
+            written: vec![0; L::LEN.div_ceil(64)],
+            count: 0,
+            _cells: PhantomData,
+        }
+    }
+
+    fn index(c: CoordType<L>) -> usize {
+        assert!(L::contains(c), "coordinates out of bounds");
+        L::index(c)
+    }
+
+    /// Write the cell at the flat index `i`, in memory order, dropping its previous value if it was already set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `i` is not less than the number of cells.
+    pub fn set_index(&mut self, i: usize, e: E) {
+        assert!(i < L::LEN, "index out of bounds");
+        let cell = unsafe { self.cells().add(i) };
+        if self.is_written(i) {
+            drop(unsafe { std::ptr::replace(cell, e) });
+        } else {
+            unsafe { cell.write(e) };
+            self.written[i / 64] |= 1 << (i % 64);
+            self.count += 1;
+        }
+    }
+
+    /// Write the cell at the given coordinates, dropping its previous value if it was already set.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are out of bounds.
+    pub fn set(&mut self, c: CoordType<L>, e: E) {
+        self.set_index(Self::index(c), e)
+    }
+
+    /// Same as `set` but with the coordinates as an array, see `boxarray_idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are out of bounds.
+    pub fn set_idx<const R: usize>(&mut self, c: [usize; R], e: E) {
+        self.set_index(Self::index(Coords::from_array(c)), e)
+    }
+
+    /// The cell at the given coordinates, or `None` if it is not set yet.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are out of bounds.
+    pub fn get(&self, c: CoordType<L>) -> Option<&E> {
+        let i = Self::index(c);
+        self.is_written(i).then(|| unsafe { &*self.cells().add(i) })
+    }
+
+    /// Same as `get` but with the coordinates as an array, see `boxarray_idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are out of bounds.
+    pub fn get_idx<const R: usize>(&self, c: [usize; R]) -> Option<&E> {
+        self.get(Coords::from_array(c))
+    }
+
+    /// Number of cells that are set.
+    pub fn count(&self) -> usize {
+        self.count
+    }
+
+    /// Whether every cell is set, so that `finish` succeeds.
+    pub fn is_complete(&self) -> bool {
+        self.count == L::LEN
+    }
+
+    /// Number of cells that are not set yet.
+    pub fn missing_count(&self) -> usize {
+        L::LEN - self.count
+    }
+
+    /// Coordinates of the cells that are not set yet, in memory order, computed lazily from the bitset so that the words of
+    /// 64 written cells are skipped.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut b = boxarray::Builder::<u8, _, [[[u8; 64]; 64]; 64]>::new();
+    /// for i in 0..64 * 64 * 64 {
+    ///     if i != 4097 {
+    ///         b.set_index(i, 0);
+    ///     }
+    /// }
+    /// assert_eq!(b.missing_count(), 1);
+    /// assert!(b.missing().eq([((((), 1), 0), 1)]));
+    /// ```
+    pub fn missing(&self) -> impl Iterator<Item = CoordType<L>> + '_ {
+        self.written
+            .iter()
+            .enumerate()
+            .flat_map(|(w, &bits)| {
+                let mut unset = !bits;
+                std::iter::from_fn(move || {
+                    (unset != 0).then(|| {
+                        let b = unset.trailing_zeros() as usize;
+                        unset &= unset - 1;
+                        64 * w + b
+                    })
+                })
+            })
+            .take_while(|&i| i < L::LEN)
+            .map(L::coords)
+    }
+
+    /// Give the initialized nested arrays if every cell is set, or the builder back otherwise.
+    pub fn finish(mut self) -> Result<Box<A>, FinishError<E, L, A>> {
+        if self.is_complete() {
+            let raw = self.raw.take().expect("builder is live");
+            Ok(unsafe { raw.assume_init() })
+        } else {
+            Err(FinishError { builder: self })
+        }
+    }
+}
+
+impl<E, L: CUList + IndexCoord<L>, A: Arrays<E, L>> Default for Builder<E, L, A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<E, L: CUList, A: Arrays<E, L>> Drop for Builder<E, L, A> {
+    fn drop(&mut self) {
+        if self.raw.is_some() {
+            for i in (0..L::LEN).filter(|i| self.is_written(*i)) {
+                unsafe { std::ptr::drop_in_place(self.cells().add(i)) };
+            }
+        }
+    }
+}
+
+impl<E, L: CUList, A: Arrays<E, L>> fmt::Debug for Builder<E, L, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Builder")
+            .field("count", &self.count)
+            .field("len", &L::LEN)
+            .finish()
+    }
+}
+
+/// Error returned by `Builder::finish` when some cells are not set, holding the builder to list and set the missing cells.
+pub struct FinishError<E, L: CUList, A: Arrays<E, L>> {
+    builder: Builder<E, L, A>,
+}
+
+impl<E, L: CUList + IndexCoord<L>, A: Arrays<E, L>> FinishError<E, L, A> {
+    /// Number of cells that are not set.
+    pub fn missing_count(&self) -> usize {
+        self.builder.missing_count()
+    }
+
+    /// Coordinates of the cells that are not set, in memory order, see `Builder::missing`.
+    pub fn missing(&self) -> impl Iterator<Item = CoordType<L>> + '_ {
+        self.builder.missing()
+    }
+
+    /// The builder.
+    pub fn builder(&self) -> &Builder<E, L, A> {
+        &self.builder
+    }
+
+    /// The builder, to set the missing cells.
+    pub fn into_builder(self) -> Builder<E, L, A> {
+        self.builder
+    }
+}
+
+impl<E, L: CUList, A: Arrays<E, L>> fmt::Debug for FinishError<E, L, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FinishError")
+            .field("builder", &self.builder)
+            .finish()
+    }
+}
+
+impl<E, L: CUList, A: Arrays<E, L>> fmt::Display for FinishError<E, L, A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let missing = L::LEN - self.builder.count;
+        write!(f, "{missing} cells are not initialized")
+    }
+}
+
+impl<E, L: CUList, A: Arrays<E, L>> std::error::Error for FinishError<E, L, A> {}
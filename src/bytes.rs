@@ -0,0 +1,169 @@
+//! Initialization fast paths relying on the byte representation of the cells.
+use crate::init::RawBox;
+use crate::private::*;
+use crate::AllocError;
+use std::num::*;
+
+/// Types for which a value with all its bytes set to zero is valid.
+///
+/// # Safety
+///
+/// The all-zero byte pattern must be a valid value of the type, such as `0` for integers, `0.0` for floats, `false`, `None`
+/// for `Option<NonZero*>` or a null raw pointer.
+pub unsafe trait Zeroable {}
+
+macro_rules! zeroable {
+    ($($t:ty),*) => {
+        $(unsafe impl Zeroable for $t {})*
+    };
+}
+zeroable!(bool, char, f32, f64, ());
+zeroable!(u8, u16, u32, u64, u128, usize);
+zeroable!(i8, i16, i32, i64, i128, isize);
+
+macro_rules! zeroable_option {
+    ($($t:ty),*) => {
+        $(unsafe impl Zeroable for Option<$t> {})*
+    };
+}
+zeroable_option!(NonZeroU8, NonZeroU16, NonZeroU32);
+zeroable_option!(NonZeroU64, NonZeroU128, NonZeroUsize);
+zeroable_option!(NonZeroI8, NonZeroI16, NonZeroI32);
+zeroable_option!(NonZeroI64, NonZeroI128, NonZeroIsize);
+unsafe impl<T> Zeroable for *const T {}
+unsafe impl<T> Zeroable for *mut T {}
+unsafe impl<T: Zeroable, const N: usize> Zeroable for [T; N] {}
+
+/// Allocate nested arrays (or any `Zeroable` type) on the heap with all their bytes set to zero.
+///
+/// This relies only on `alloc_zeroed` and never writes the cells. Whether the memory is touched depends on the allocator:
+/// some of them map fresh pages already zeroed by the operating system for large allocations, while others zero memory
+/// they reuse. Calls `handle_alloc_error` if the allocation fails.
+///
+/// # Examples
+///
+/// ```
+/// let a: Box<[[[f64; 3]; 2]; 4]> = boxarray::boxarray_zeroed();
+/// assert_eq!(*a, [[[0.0; 3]; 2]; 4]);
+///
+/// let a: Box<[[Option<std::num::NonZeroU32>; 4]; 4]> = boxarray::boxarray_zeroed();
+/// assert!(a.iter().flatten().all(Option::is_none));
+/// ```
+///
+/// Types for which zero bytes are not a valid value are rejected.
+/// ```compile_fail
+/// let a: Box<[[std::num::NonZeroU32; 4]; 4]> = boxarray::boxarray_zeroed();
+/// ```
+/// ```compile_fail
+/// let a: Box<[[&u32; 4]; 4]> = boxarray::boxarray_zeroed();
+/// ```
+pub fn boxarray_zeroed<A: Zeroable>() -> Box<A> {
+    unsafe { RawBox::new_zeroed().assume_init() }
+}
+
+/// Same as `boxarray_zeroed` but return an `AllocError` if the allocation fails instead of calling `handle_alloc_error`.
+///
+/// # Examples
+///
+/// ```
+/// let a: Result<Box<[[u16; 64]; 64]>, _> = boxarray::try_boxarray_zeroed();
+/// assert_eq!(*a.unwrap(), [[0; 64]; 64]);
+///
+/// let a: Result<Box<[[u8; 1 << 30]; 1 << 30]>, _> = boxarray::try_boxarray_zeroed();
+/// assert!(a.is_err());
+/// ```
+pub fn try_boxarray_zeroed<A: Zeroable>() -> Result<Box<A>, AllocError> {
+    Ok(unsafe { RawBox::try_new_zeroed()?.assume_init() })
+}
+
+/// `Copy` types whose bytes are always all initialized, which means that they have no padding.
+///
+/// # Safety
+///
+/// Every byte of every value of the type must be initialized, and any copy of these bytes must be a valid value.
+pub unsafe trait NoUninit: Copy {}
+
+macro_rules! no_uninit {
+    ($($t:ty),*) => {
+        $(unsafe impl NoUninit for $t {})*
+    };
+}
+no_uninit!(bool, char, f32, f64, ());
+no_uninit!(u8, u16, u32, u64, u128, usize);
+no_uninit!(i8, i16, i32, i64, i128, isize);
+no_uninit!(NonZeroU8, NonZeroU16, NonZeroU32);
+no_uninit!(NonZeroU64, NonZeroU128, NonZeroUsize);
+no_uninit!(NonZeroI8, NonZeroI16, NonZeroI32);
+no_uninit!(NonZeroI64, NonZeroI128, NonZeroIsize);
+unsafe impl<T: NoUninit, const N: usize> NoUninit for [T; N] {}
+
+/// Same as `boxarray` but specialized for `NoUninit` cells, which are filled with a memory copy instead of a `clone` per cell.
+///
+/// When all the bytes of `e` are equal, such as for `0`, `-1` or `[7u8; 4]`, the memory is filled with `write_bytes`.
+/// Otherwise, `e` is written once and the initialized part is copied after itself, doubling its length until it makes a block
+/// of 16 KiB that stays in the cache, and that block is then copied forward until the end.
+///
+/// # Examples
+///
+/// ```
+/// let a: Box<[[[f64; 10]; 2]; 4]> = boxarray::boxarray_copy(7.0);
+/// assert_eq!(*a, [[[7.0; 10]; 2]; 4]);
+///
+/// let a: Box<[[u32; 5]; 3]> = boxarray::boxarray_copy(u32::MAX);
+/// assert_eq!(*a, [[u32::MAX; 5]; 3]);
+///
+/// let a: Box<[[f64; 100]; 101]> = boxarray::boxarray_copy(-2.5);
+/// assert_eq!(*a, [[-2.5; 100]; 101]);
+/// ```
+///
+/// The element type can be an array itself, in which case it is copied as a whole.
+/// ```
+/// let a: Box<[[[u16; 3]; 7]; 5]> = boxarray::boxarray_copy::<[u16; 3], _, _>([1, 2, 3]);
+/// assert!(a.iter().flatten().all(|e| *e == [1, 2, 3]));
+/// ```
+///
+/// Types that may contain padding bytes are rejected, use `boxarray` instead.
+/// ```compile_fail
+/// let a: Box<[[(u8, u32); 4]; 4]> = boxarray::boxarray_copy((1, 2));
+/// ```
+pub fn boxarray_copy<E: NoUninit, L: CUList + Product<usize>, A: Arrays<E, L>>(e: E) -> Box<A> {
+    let raw = RawBox::<A>::new();
+    unsafe {
+        fill_copy(raw.as_mut_ptr() as *mut E, L::product(), e);
+        raw.assume_init()
+    }
+}
+
+/// Size in bytes of the block built by doubling in `fill_copy`, small enough to stay in the L1 cache while the rest of the
+/// memory is filled by copying it forward.
+const COPY_BLOCK: usize = 16 * 1024;
+
+/// Write `n` copies of `e` into `ptr`.
+///
+/// # Safety
+///
+/// `ptr` must be valid for writes of `n` consecutive values of type `E`.
+unsafe fn fill_copy<E: NoUninit>(ptr: *mut E, n: usize, e: E) {
+    let size = std::mem::size_of::<E>();
+    if n == 0 || size == 0 {
+        return;
+    }
+    let bytes = std::slice::from_raw_parts(&e as *const E as *const u8, size);
+    if bytes.iter().all(|b| *b == bytes[0]) {
+        std::ptr::write_bytes(ptr, bytes[0], n);
+    } else {
+        ptr.write(e);
+        let block = (COPY_BLOCK / size).clamp(1, n);
+        let mut len = 1;
+        while len < block {
+            let count = len.min(block - len);
+            std::ptr::copy_nonoverlapping(ptr, ptr.add(len), count);
+            len += count;
+        }
+        while len < n {
+            let count = block.min(n - len);
+            std::ptr::copy_nonoverlapping(ptr, ptr.add(len), count);
+            len += count;
+        }
+    }
+}
@@ -0,0 +1,192 @@
+//! Views of nested arrays as flat slices of their cells.
+use crate::private::*;
+
+/// View nested arrays as a flat slice of their cells, in memory order.
+///
+/// # Examples
+///
+/// ```
+/// let a: Box<[[[f64; 3]; 2]; 4]> = boxarray::boxarray_idx(|[i, j, k]| (i + 3 * j + 6 * k) as f64);
+/// let flat: &[f64] = boxarray::as_flat(&*a);
+/// assert_eq!(flat.len(), 24);
+/// assert!(flat.iter().enumerate().all(|(i, x)| *x == i as f64));
+/// ```
+///
+/// The element type can be an array itself, in which case only the outer arrays are flattened.
+/// ```
+/// let a = [[[1, 2], [3, 4]], [[5, 6], [7, 8]]];
+/// let flat: &[[i32; 2]] = boxarray::as_flat(&a);
+/// assert_eq!(flat, [[1, 2], [3, 4], [5, 6], [7, 8]]);
+/// ```
+pub fn as_flat<E, L: CUList + Product<usize>, A: Arrays<E, L>>(a: &A) -> &[E] {
+    unsafe { std::slice::from_raw_parts(a as *const A as *const E, L::product()) }
+}
+
+/// Same as `as_flat` but return a mutable slice.
+///
+/// # Examples
+///
+/// ```
+/// let mut a: Box<[[u32; 3]; 2]> = boxarray::boxarray(0);
+/// for (i, x) in boxarray::as_flat_mut::<u32, _, _>(&mut *a).iter_mut().enumerate() {
+///     *x = i as u32;
+/// }
+/// assert_eq!(*a, [[0, 1, 2], [3, 4, 5]]);
+/// ```
+pub fn as_flat_mut<E, L: CUList + Product<usize>, A: Arrays<E, L>>(a: &mut A) -> &mut [E] {
+    unsafe { std::slice::from_raw_parts_mut(a as *mut A as *mut E, L::product()) }
+}
+
+/// Convert boxed nested arrays into a boxed flat slice of their cells, in memory order, reusing the allocation.
+///
+/// # Examples
+///
+/// ```
+/// let a: Box<[[String; 2]; 2]> = boxarray::boxarray_idx(|[i, j]| format!("{j}{i}"));
+/// let flat: Box<[String]> = boxarray::into_flat(a);
+/// assert_eq!(*flat, ["00", "01", "10", "11"]);
+/// ```
+pub fn into_flat<E, L: CUList + Product<usize>, A: Arrays<E, L>>(a: Box<A>) -> Box<[E]> {
+    let ptr = Box::into_raw(a) as *mut E;
+    unsafe { Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr, L::product())) }
+}
+
+/// Convert a boxed slice into boxed nested arrays, reusing the allocation.
+///
+/// The slice is returned as the error if its length is not the number of cells of the nested arrays.
+///
+/// # Examples
+///
+/// ```
+/// let s: Box<[u32]> = (0..6).collect();
+/// let a: Result<Box<[[u32; 3]; 2]>, _> = boxarray::try_from_boxed_slice(s);
+/// assert_eq!(*a.unwrap(), [[0, 1, 2], [3, 4, 5]]);
+///
+/// let s: Box<[u32]> = (0..5).collect();
+/// let a: Result<Box<[[u32; 3]; 2]>, _> = boxarray::try_from_boxed_slice(s);
+/// assert_eq!(*a.unwrap_err(), [0, 1, 2, 3, 4]);
+/// ```
+pub fn try_from_boxed_slice<E, L: CUList + Product<usize>, A: Arrays<E, L>>(
+    s: Box<[E]>,
+) -> Result<Box<A>, Box<[E]>> {
+    if s.len() == L::product() {
+        Ok(unsafe { Box::from_raw(Box::into_raw(s) as *mut A) })
+    } else {
+        Err(s)
+    }
+}
+
+/// Convert a `Vec` into boxed nested arrays, reusing the allocation.
+///
+/// Both the length and the capacity of the `Vec` must be the number of cells of the nested arrays, so that no reallocation is
+/// needed, otherwise the `Vec` is returned as the error. Use `Vec::shrink_to_fit` beforehand to accept a larger capacity at
+/// the cost of a possible reallocation.
+///
+/// # Examples
+///
+/// ```
+/// let v: Vec<f64> = (0..24).map(|i| i as f64).collect();
+/// let a: Box<[[[f64; 3]; 2]; 4]> = boxarray::try_from_vec(v).unwrap();
+/// assert_eq!(a[3][1][2], 23.0);
+/// ```
+///
+/// The original `Vec` is given back when it does not match.
+/// ```
+/// let v = vec![1u8; 7];
+/// let a: Result<Box<[[u8; 4]; 2]>, _> = boxarray::try_from_vec(v);
+/// assert_eq!(a.unwrap_err(), vec![1u8; 7]);
+///
+/// let mut v = Vec::with_capacity(16);
+/// v.extend([1u8; 8]);
+/// let a: Result<Box<[[u8; 4]; 2]>, _> = boxarray::try_from_vec(v);
+/// let mut v = a.unwrap_err();
+/// v.shrink_to_fit();
+/// let a: Result<Box<[[u8; 4]; 2]>, _> = boxarray::try_from_vec(v);
+/// assert_eq!(*a.unwrap(), [[1; 4]; 2]);
+/// ```
+pub fn try_from_vec<E, L: CUList + Product<usize>, A: Arrays<E, L>>(
+    v: Vec<E>,
+) -> Result<Box<A>, Vec<E>> {
+    let exact_capacity = std::mem::size_of::<E>() == 0 || v.capacity() == v.len();
+    if v.len() == L::product() && exact_capacity {
+        try_from_boxed_slice(v.into_boxed_slice()).map_err(Vec::from)
+    } else {
+        Err(v)
+    }
+}
+
+/// Reinterpret boxed nested arrays as other nested arrays with the same element type and number of cells, reusing the
+/// allocation. The cells keep their memory order.
+///
+/// The element type `E` is given first, as nested arrays can be seen as arrays of several element types, and the number of
+/// cells is checked at compile time.
+///
+/// # Examples
+///
+/// ```
+/// let a: Box<[[f64; 64]; 64]> = boxarray::boxarray_idx(|[i, j]| (i + 64 * j) as f64);
+/// let b = boxarray::reshape::<f64, _, _, [f64; 4096]>(a);
+/// assert_eq!(b[4095], 4095.0);
+/// let c: Box<[[[f64; 16]; 4]; 64]> = boxarray::reshape::<f64, _, _, _>(b);
+/// assert_eq!(c[63][3][15], 4095.0);
+/// ```
+///
+/// Any element type can be reshaped, including arrays taken as a whole.
+/// ```
+/// use std::num::FpCategory;
+///
+/// let a: Box<[[FpCategory; 4]; 4]> = boxarray::boxarray(FpCategory::Zero);
+/// let b = boxarray::reshape::<FpCategory, _, _, [FpCategory; 16]>(a);
+/// assert_eq!(*b, [FpCategory::Zero; 16]);
+///
+/// let a: Box<[[[u8; 3]; 4]; 2]> = boxarray::boxarray_idx(|[i, j, k]| (i + 3 * j + 12 * k) as u8);
+/// let b = boxarray::reshape::<[u8; 3], _, _, [[u8; 3]; 8]>(a);
+/// assert_eq!(b[5], [15, 16, 17]);
+/// ```
+///
+/// Fails to compile when the number of cells differs.
+/// ```compile_fail
+/// let a: Box<[[f64; 64]; 64]> = boxarray::boxarray(0.0);
+/// let b = boxarray::reshape::<f64, _, _, [f64; 4095]>(a);
+/// ```
+///
+/// Fails to compile when the element type differs.
+/// ```compile_fail
+/// let a: Box<[[f32; 64]; 64]> = boxarray::boxarray(0.0);
+/// let b = boxarray::reshape::<f64, _, _, [f64; 2048]>(a);
+/// ```
+pub fn reshape<E, LA: CUList, LB: CUList, B: Arrays<E, LB>>(a: Box<impl Arrays<E, LA>>) -> Box<B> {
+    const { assert!(LA::LEN == LB::LEN, "reshape must keep the number of cells") };
+    unsafe { Box::from_raw(Box::into_raw(a) as *mut B) }
+}
+
+/// Same as `reshape` but for a shared reference.
+///
+/// # Examples
+///
+/// ```
+/// let a = [[1, 2, 3], [4, 5, 6]];
+/// let b = boxarray::reshape_ref::<i32, _, _, [[i32; 2]; 3]>(&a);
+/// assert_eq!(*b, [[1, 2], [3, 4], [5, 6]]);
+/// ```
+pub fn reshape_ref<E, LA: CUList, LB: CUList, B: Arrays<E, LB>>(a: &impl Arrays<E, LA>) -> &B {
+    const { assert!(LA::LEN == LB::LEN, "reshape must keep the number of cells") };
+    unsafe { &*(a as *const _ as *const B) }
+}
+
+/// Same as `reshape` but for a mutable reference.
+///
+/// # Examples
+///
+/// ```
+/// let mut a: Box<[[u8; 4]; 4]> = boxarray::boxarray(0);
+/// let flat = boxarray::reshape_mut::<u8, _, _, [u8; 16]>(&mut *a);
+/// flat[5] = 1;
+/// assert_eq!(a[1][1], 1);
+/// ```
+pub fn reshape_mut<E, LA: CUList, LB: CUList, B: Arrays<E, LB>>(
+    a: &mut impl Arrays<E, LA>,
+) -> &mut B {
+    const { assert!(LA::LEN == LB::LEN, "reshape must keep the number of cells") };
+    unsafe { &mut *(a as *mut _ as *mut B) }
+}
@@ -0,0 +1,372 @@
+//! Guarded initialization core shared by every constructor of the crate.
+//!
+//! Values are written into uninitialized memory one after the other while counting how many were completed, so that if the
+//! producer panics exactly those are dropped and the allocation is freed.
+use std::{
+    alloc::{alloc, alloc_zeroed, dealloc, handle_alloc_error, Layout},
+    convert::Infallible,
+    fmt,
+    mem::ManuallyDrop,
+    ptr,
+};
+
+/// Error returned when the allocator could not provide the memory for a nested array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AllocError {
+    layout: Layout,
+}
+
+impl AllocError {
+    /// Layout of the allocation that failed.
+    pub fn layout(&self) -> Layout {
+        self.layout
+    }
+}
+
+impl fmt::Display for AllocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "memory allocation of {} bytes (aligned to {}) failed",
+            self.layout.size(),
+            self.layout.align()
+        )
+    }
+}
+
+impl std::error::Error for AllocError {}
+
+/// Error returned when an iterator does not yield exactly one item per cell of a nested array.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FromIterError {
+    /// The iterator ended before every cell was initialized, `missing` more items were needed.
+    TooFew { missing: usize },
+    /// The iterator still had items after every cell was initialized.
+    TooMany,
+}
+
+impl fmt::Display for FromIterError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FromIterError::TooFew { missing } => {
+                write!(f, "iterator is {missing} items too short to fill the array")
+            }
+            FromIterError::TooMany => write!(f, "iterator is too long for the array"),
+        }
+    }
+}
+
+impl std::error::Error for FromIterError {}
+
+/// Error returned when the function initializing a nested array fails, along with the coordinates of the failing cell.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InitError<C, E> {
+    coord: C,
+    error: E,
+}
+
+impl<C, E> InitError<C, E> {
+    pub(crate) fn new(coord: C, error: E) -> Self {
+        InitError { coord, error }
+    }
+
+    /// Coordinates of the cell whose initialization failed.
+    pub fn coord(&self) -> &C {
+        &self.coord
+    }
+
+    /// Error returned by the initialization function.
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+
+    /// Split into the coordinates of the failing cell and the error.
+    pub fn into_parts(self) -> (C, E) {
+        (self.coord, self.error)
+    }
+}
+
+impl<C: fmt::Debug, E: fmt::Display> fmt::Display for InitError<C, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "initialization failed at {:?}: {}",
+            self.coord, self.error
+        )
+    }
+}
+
+impl<C: fmt::Debug, E: std::error::Error + 'static> std::error::Error for InitError<C, E> {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.error)
+    }
+}
+
+/// Uninitialized heap allocation for a value of type `A`, freed when dropped.
+pub(crate) struct RawBox<A> {
+    ptr: *mut A,
+    layout: Layout,
+}
+
+impl<A> RawBox<A> {
+    /// Allocate an uninitialized `A` with the global allocator, calling `handle_alloc_error` on failure.
+    pub(crate) fn new() -> Self {
+        Self::try_new().unwrap_or_else(|e| handle_alloc_error(e.layout))
+    }
+
+    /// Allocate an uninitialized `A` with the global allocator.
+    ///
+    /// Nothing is allocated when `A` is zero-sized, a dangling aligned pointer is used instead.
+    pub(crate) fn try_new() -> Result<Self, AllocError> {
+        Self::try_new_with(Layout::new::<A>(), alloc)
+    }
+
+    /// Allocate an uninitialized `A` with the global allocator and the given layout, which must fit an `A`, calling
+    /// `handle_alloc_error` on failure.
+    pub(crate) fn with_layout(layout: Layout) -> Self {
+        debug_assert!(layout.size() == size_of::<A>() && layout.align() >= align_of::<A>());
+        Self::try_new_with(layout, alloc).unwrap_or_else(|e| handle_alloc_error(e.layout))
+    }
+
+    /// Same as `try_new` but with all the bytes set to zero, calling `handle_alloc_error` on failure.
+    pub(crate) fn new_zeroed() -> Self {
+        Self::try_new_zeroed().unwrap_or_else(|e| handle_alloc_error(e.layout))
+    }
+
+    /// Same as `try_new` but with all the bytes set to zero.
+    pub(crate) fn try_new_zeroed() -> Result<Self, AllocError> {
+        Self::try_new_with(Layout::new::<A>(), alloc_zeroed)
+    }
+
+    fn try_new_with(
+        layout: Layout,
+        allocate: unsafe fn(Layout) -> *mut u8,
+    ) -> Result<Self, AllocError> {
+        let ptr = if layout.size() == 0 {
+            ptr::without_provenance_mut(layout.align())
+        } else {
+            unsafe { allocate(layout) as *mut A }
+        };
+        if ptr.is_null() {
+            Err(AllocError { layout })
+        } else {
+            Ok(RawBox { ptr, layout })
+        }
+    }
+
+    pub(crate) fn as_mut_ptr(&self) -> *mut A {
+        self.ptr
+    }
+
+    /// Convert the allocation into a `Box` without freeing it.
+    ///
+    /// # Safety
+    ///
+    /// The allocation must hold a fully initialized `A`.
+    pub(crate) unsafe fn assume_init(self) -> Box<A> {
+        debug_assert!(self.layout == Layout::new::<A>());
+        Box::from_raw(self.into_raw())
+    }
+
+    /// Give up the ownership of the allocation, which must then be freed with the same layout.
+    pub(crate) fn into_raw(self) -> *mut A {
+        ManuallyDrop::new(self).ptr
+    }
+}
+
+impl<A> Drop for RawBox<A> {
+    fn drop(&mut self) {
+        if self.layout.size() != 0 {
+            unsafe { dealloc(self.ptr as *mut u8, self.layout) }
+        }
+    }
+}
+
+/// Prefix of a buffer of `E` that has already been written, dropped in place when the guard is dropped.
+struct Partial<E> {
+    ptr: *mut E,
+    len: usize,
+}
+
+impl<E> Drop for Partial<E> {
+    fn drop(&mut self) {
+        unsafe { ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.ptr, self.len)) }
+    }
+}
+
+/// Write the `n` values returned by `f` for the flat indices `0..n` into `ptr`, in memory order.
+///
+/// If `f` panics, the values already written are dropped before unwinding further.
+///
+/// # Safety
+///
+/// `ptr` must be valid for writes of `n` consecutive values of type `E`.
+pub(crate) unsafe fn fill<E>(ptr: *mut E, n: usize, mut f: impl FnMut(usize) -> E) {
+    match try_fill(ptr, n, |i| Ok::<_, Infallible>(f(i))) {
+        Ok(()) => {}
+        Err((_, e)) => match e {},
+    }
+}
+
+/// Same as [`fill`] but stop at the first error returned by `f`, drop the values already written and return the error along
+/// with the flat index it occurred at.
+///
+/// # Safety
+///
+/// `ptr` must be valid for writes of `n` consecutive values of type `E`.
+pub(crate) unsafe fn try_fill<E, Err>(
+    ptr: *mut E,
+    n: usize,
+    mut f: impl FnMut(usize) -> Result<E, Err>,
+) -> Result<(), (usize, Err)> {
+    let mut partial = Partial { ptr, len: 0 };
+    while partial.len < n {
+        let e = f(partial.len).map_err(|err| (partial.len, err))?;
+        ptr.add(partial.len).write(e);
+        partial.len += 1;
+    }
+    std::mem::forget(partial);
+    Ok(())
+}
+
+/// Allocate an `A` made of `n` values of type `E` and initialize it with `f` through [`fill`].
+///
+/// If `f` panics, the values already written are dropped and the allocation is freed.
+///
+/// # Safety
+///
+/// `A` must consist of exactly `n` consecutive values of type `E`.
+pub(crate) unsafe fn init<E, A>(n: usize, f: impl FnMut(usize) -> E) -> Box<A> {
+    init_in(RawBox::new(), n, f)
+}
+
+/// Same as [`init`] but return an error instead of aborting when the allocation fails.
+///
+/// # Safety
+///
+/// `A` must consist of exactly `n` consecutive values of type `E`.
+pub(crate) unsafe fn try_init<E, A>(
+    n: usize,
+    f: impl FnMut(usize) -> E,
+) -> Result<Box<A>, AllocError> {
+    Ok(init_in(RawBox::try_new()?, n, f))
+}
+
+/// Same as [`init`] but initialize through [`try_fill`], freeing the allocation if `f` returns an error.
+///
+/// # Safety
+///
+/// `A` must consist of exactly `n` consecutive values of type `E`.
+pub(crate) unsafe fn init_with<E, A, Err>(
+    n: usize,
+    f: impl FnMut(usize) -> Result<E, Err>,
+) -> Result<Box<A>, (usize, Err)> {
+    let raw = RawBox::<A>::new();
+    try_fill(raw.as_mut_ptr() as *mut E, n, f)?;
+    Ok(raw.assume_init())
+}
+
+/// Same as [`init`] but allocate with the given layout, which must fit an `A`, and return the raw pointer to the
+/// allocation, which must then be freed with the same layout.
+///
+/// # Safety
+///
+/// `A` must consist of exactly `n` consecutive values of type `E`.
+pub(crate) unsafe fn init_raw<E, A>(layout: Layout, n: usize, f: impl FnMut(usize) -> E) -> *mut A {
+    let raw = RawBox::<A>::with_layout(layout);
+    fill(raw.as_mut_ptr() as *mut E, n, f);
+    raw.into_raw()
+}
+
+unsafe fn init_in<E, A>(raw: RawBox<A>, n: usize, f: impl FnMut(usize) -> E) -> Box<A> {
+    fill(raw.as_mut_ptr() as *mut E, n, f);
+    raw.assume_init()
+}
+
+/// Raw pointer shared between the threads initializing disjoint parts of a buffer.
+struct SendPtr<E>(*mut E);
+unsafe impl<E: Send> Send for SendPtr<E> {}
+unsafe impl<E: Send> Sync for SendPtr<E> {}
+impl<E> SendPtr<E> {
+    fn get(&self) -> *mut E {
+        self.0
+    }
+}
+
+/// Chunks of a buffer of `n` values of type `E` that have been completely initialized, dropped in place when the guard is
+/// dropped.
+struct Chunks<'a, E> {
+    ptr: *mut E,
+    n: usize,
+    chunk: usize,
+    done: &'a [std::sync::atomic::AtomicBool],
+}
+
+impl<E> Drop for Chunks<'_, E> {
+    fn drop(&mut self) {
+        for (c, done) in self.done.iter().enumerate() {
+            if done.load(std::sync::atomic::Ordering::Acquire) {
+                let start = c * self.chunk;
+                let len = self.chunk.min(self.n - start);
+                unsafe {
+                    ptr::drop_in_place(ptr::slice_from_raw_parts_mut(self.ptr.add(start), len))
+                }
+            }
+        }
+    }
+}
+
+/// Write `n` values into `ptr` by chunks of `chunk` values, where `fill_chunk(start, ptr, len)` must initialize the `len`
+/// values at `ptr`, which start at the flat index `start`, through [`fill`].
+///
+/// The chunks are initialized by calling the function given to `for_each` with each chunk index from `0` to the number of
+/// chunks. `for_each` can run them in parallel but must only return, or unwind, once all of them are finished. If one of them
+/// panics, the values of the chunks already initialized are dropped before unwinding further.
+///
+/// # Safety
+///
+/// `ptr` must be valid for writes of `n` consecutive values of type `E`.
+pub(crate) unsafe fn fill_chunks<E: Send>(
+    ptr: *mut E,
+    n: usize,
+    chunk: usize,
+    for_each: impl FnOnce(usize, &(dyn Fn(usize) + Sync)),
+    fill_chunk: impl Fn(usize, *mut E, usize) + Sync,
+) {
+    let chunk = chunk.max(1);
+    let done: Vec<_> = (0..n.div_ceil(chunk))
+        .map(|_| std::sync::atomic::AtomicBool::new(false))
+        .collect();
+    let chunks = Chunks {
+        ptr,
+        n,
+        chunk,
+        done: &done,
+    };
+    let shared = SendPtr(ptr);
+    for_each(done.len(), &|c| {
+        let ptr = shared.get();
+        let start = c * chunk;
+        fill_chunk(start, ptr.add(start), chunk.min(n - start));
+        done[c].store(true, std::sync::atomic::Ordering::Release);
+    });
+    std::mem::forget(chunks);
+}
+
+/// Allocate an `A` made of `n` values of type `E` and initialize it through [`fill_chunks`].
+///
+/// If a chunk panics, the values already written are dropped and the allocation is freed.
+///
+/// # Safety
+///
+/// `A` must consist of exactly `n` consecutive values of type `E`.
+pub(crate) unsafe fn init_chunks<E: Send, A>(
+    n: usize,
+    chunk: usize,
+    for_each: impl FnOnce(usize, &(dyn Fn(usize) + Sync)),
+    fill_chunk: impl Fn(usize, *mut E, usize) + Sync,
+) -> Box<A> {
+    let raw = RawBox::<A>::new();
+    fill_chunks(raw.as_mut_ptr() as *mut E, n, chunk, for_each, fill_chunk);
+    raw.assume_init()
+}
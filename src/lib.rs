@@ -13,17 +13,133 @@
 //!   let f = |((((), i), j), k)| (i+j*k) as usize;
 //!   let a: Box<[[[usize; 3]; 2]; 4]> = boxarray::boxarray_(f);
 //! ```
-use std::{
-    alloc::{alloc_zeroed, Layout},
-    mem::transmute,
-};
+//!
+//! The coordinates can also be received as an array `[i, j, k]` with the `_idx` variants, which is easier to use in code that is generic over the number of dimensions:
+//! ```
+//!   let a: Box<[[[usize; 3]; 2]; 4]> = boxarray::boxarray_idx(|[i, j, k]| i + j * k);
+//! ```
+//!
+//! Both the nested tuples and the arrays above list the inner-most index first, so `((((), i), j), k)` and `[i, j, k]` designate the cell `a[k][j][i]`.
+//! Use `boxarray_outer_first` to receive the coordinates in the same order as the indexing instead:
+//! ```
+//!   let a: Box<[[[usize; 3]; 2]; 4]> = boxarray::boxarray_outer_first(|[k, j, i]| i + j * k);
+//!   assert_eq!(a[3][1][2], 2 + 1 * 3);
+//! ```
+//!
+//! When the cells are arrays themselves, such as a field of 3-vectors, the number of array nesting to consider can be fixed with `Rank`:
+//! ```
+//!   let a: Box<[[[f64; 3]; 100]; 100]> = boxarray::Rank::<2>::boxarray_(|(((), i), j)| [i as f64, j as f64, 0.0]);
+//! ```
+//!
+//! When computing a cell can fail, `try_boxarray_with` stops at the first error and reports it with the coordinates of the cell:
+//! ```
+//!   let words = ["1", "2", "x", "4"];
+//!   let a: Result<Box<[f64; 4]>, _> = boxarray::try_boxarray_with(|((), i): ((), usize)| words[i].parse::<f64>());
+//!   assert_eq!(*a.unwrap_err().coord(), ((), 2));
+//! ```
+//!
+//! Stateful initialization is possible with `boxarray_from_fn_mut`, which calls the function in memory order, or by consuming an iterator with `boxarray_from_iter`:
+//! ```
+//!   let a: Result<Box<[[u32; 3]; 2]>, _> = boxarray::boxarray_from_iter(1..=6);
+//!   assert_eq!(*a.unwrap(), [[1, 2, 3], [4, 5, 6]]);
+//! ```
+//!
+//! Cells that are valid when all their bytes are zero, as marked by the `Zeroable` trait, can be obtained directly from `alloc_zeroed` without writing them one by one:
+//! ```
+//!   let a: Box<[[[f64; 3]; 2]; 4]> = boxarray::boxarray_zeroed();
+//! ```
+//!
+//! Cells can also be set in any order with a `Builder`, which tracks the ones that are written and only gives the nested
+//! arrays once they are all set:
+//! ```
+//!   let mut b = boxarray::Builder::<u8, _, [[u8; 2]; 2]>::new();
+//!   b.set_idx([1, 1], 3);
+//!   b.set_idx([0, 0], 0);
+//!   assert!(b.missing().eq([(((), 1), 0), (((), 0), 1)]));
+//!   b.set_idx([0, 1], 2);
+//!   b.set_idx([1, 0], 1);
+//!   assert_eq!(*b.finish().unwrap(), [[0, 1], [2, 3]]);
+//! ```
+//!
+//! The cells can be initialized in parallel, with the same results, by `boxarray_threaded` and `boxarray_threaded_` which only
+//! rely on the threads of the standard library, or by `par_boxarray` and `par_boxarray_` with the `rayon` feature:
+//! ```
+//!   let a: Box<[[[usize; 3]; 2]; 4]> = boxarray::boxarray_threaded_(2, |((((), i), j), k)| (i + j * k) as usize);
+//! ```
+//!
+//! With the `rand` feature, `boxarray_random` and its variants fill the cells with random values that only depend on a seed
+//! and on the position of each cell, so the serial and parallel versions give the same arrays:
+//! ```
+//!   # #[cfg(feature = "rand")] {
+//!   let a: Box<[[f64; 100]; 100]> = boxarray::boxarray_normal(42, 0.0, 1.0);
+//!   let b: Box<[[f64; 100]; 100]> = boxarray::boxarray_random_threaded::<f64, _, _, _>(4, 42, boxarray::rand_distr::StandardNormal);
+//!   assert_eq!(a, b);
+//!   # }
+//! ```
+//!
+//! With the `allocator_api` feature on nightly, or the `allocator-api2` feature on stable, `boxarray_in` and `boxarray_in_`
+//! allocate the nested arrays with any `Allocator`, such as an arena or a huge-page allocator, and return a `Box<A, Alloc>`.
+//!
+//! When only the outer-most length is known at run time, `boxslice` and `boxslice_` allocate a `Box<[A]>` of nested arrays,
+//! the function receiving the index in the slice as the last coordinate:
+//! ```
+//!   let frames: Box<[[[f32; 3]; 64]]> = boxarray::boxslice_(10, |((((), i), j), k)| (i + j * k) as f32);
+//!   assert_eq!(frames.len(), 10);
+//! ```
+//!
+//! A `BoxArrayVec` grows the same way, each pushed nested array being initialized in place in the vector:
+//! ```
+//!   let mut frames = boxarray::BoxArrayVec::<[[f32; 512]; 512]>::new();
+//!   frames.push_with(|(((), i), j)| (i + j) as f32);
+//!   frames.push_fill(0.0);
+//! ```
+//!
+//! Read-only tables meant to be shared can be initialized directly inside an `Rc` or an `Arc` with `rcarray`, `rcarray_`,
+//! `arcarray` and `arcarray_`:
+//! ```
+//!   let a: std::sync::Arc<[[f64; 1024]; 1024]> = boxarray::arcarray_(|(((), i), j)| (i * j) as f64);
+//! ```
+//!
+//! Kernels and buffers that need more than the alignment of the element type, such as 64 bytes for AVX-512 or 4096 bytes
+//! for pages, can get an `AlignedBox` from `boxarray_aligned` and `boxarray_aligned_`:
+//! ```
+//!   let a: boxarray::AlignedBox<[[f32; 1024]; 1024], 64> = boxarray::boxarray_aligned(0.0);
+//!   assert_eq!(a.as_ptr() as usize % 64, 0);
+//! ```
+//!
+//! All of them call `handle_alloc_error` when the memory cannot be allocated. Use `try_boxarray` and `try_boxarray_` to get an `AllocError` instead:
+//! ```
+//!   let a: Result<Box<[[[f64; 3]; 2]; 4]>, _> = boxarray::try_boxarray(7.0);
+//!   assert!(a.is_ok());
+//! ```
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+
+mod aligned;
+#[cfg(any(feature = "allocator_api", feature = "allocator-api2"))]
+mod allocator;
+mod builder;
+mod bytes;
+mod flat;
+mod init;
+#[cfg(feature = "rayon")]
+mod par;
+#[cfg(feature = "rand")]
+mod random;
+mod rank;
+mod shape;
+mod shared;
+mod slice;
+mod threaded;
+mod vec;
 
 mod private {
     use std::marker::PhantomData;
 
     /// Type-level list of const generic usize.
     pub trait CUList {
-        type CoordType;
+        type CoordType: Coords;
+        /// Number of values, which is the product of the sizes of the arrays, known at compile time.
+        const LEN: usize;
     }
     /// Type operator that return the CoordType of o CUList, which is a type representing nested tuple of usize, where the number of nesting is the same as the number of array nesting the CUList represent.
     pub type CoordType<A> = <A as CUList>::CoordType;
@@ -32,6 +148,7 @@ mod private {
     pub struct Value {}
     impl CUList for Value {
         type CoordType = ();
+        const LEN: usize = 1;
     }
 
     /// Array constructor for `CUList`. Represent the outter-most array that contains the other nested arrays and its own size.
@@ -40,28 +157,119 @@ mod private {
     }
     impl<L: CUList, const N: usize> CUList for Array<L, N> {
         type CoordType = (L::CoordType, usize);
+        const LEN: usize = N * L::LEN;
     }
 
-    /// Convert the impl type to a value of type `T`.
-    pub trait Reify<T> {
-        fn reify() -> T;
-    }
+    /// Prevent implementations of the public traits outside of this crate.
+    pub trait Sealed {}
+    /// Prevent implementations of `Shape` outside of this crate.
+    pub trait ShapeSealed<E> {}
+    /// Prevent implementations of `Ranked` outside of this crate.
+    pub trait RankedSealed<const R: usize> {}
 
-    impl Reify<usize> for Value {
-        fn reify() -> usize {
-            0
+    /// Nested tuples of `usize` used as coordinates, such as `((((), i), j), k)` where `i` indexes the inner-most array.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use boxarray::Coords;
+    ///
+    /// let c = ((((), 1), 2), 3);
+    /// assert_eq!(<((((), usize), usize), usize)>::RANK, 3);
+    /// assert_eq!(c.to_array(), [1, 2, 3]);
+    /// let d: ((((), usize), usize), usize) = Coords::from_array([1, 2, 3]);
+    /// assert_eq!(d, c);
+    /// assert_eq!(c.to_outer_first(), [3, 2, 1]);
+    /// ```
+    ///
+    /// The length of the array must match the number of coordinates.
+    /// ```compile_fail
+    /// use boxarray::Coords;
+    ///
+    /// let a: [usize; 2] = ((((), 1), 2), 3).to_array();
+    /// ```
+    pub trait Coords: Sealed + Copy {
+        /// Number of coordinates, which is the number of tuple nesting. This is the single source of the rank of nested
+        /// arrays, which is the `RANK` of their `CoordType`.
+        const RANK: usize;
+
+        /// Convert to an array of coordinates in the same order, such that `((((), i), j), k)` becomes `[i, j, k]`.
+        ///
+        /// Fails to compile if `R` is not `Self::RANK`.
+        fn to_array<const R: usize>(self) -> [usize; R] {
+            const {
+                assert!(
+                    R == Self::RANK,
+                    "the array length must be the number of coordinates"
+                )
+            };
+            let mut a = [0; R];
+            self.write(&mut a);
+            a
+        }
+
+        /// Convert from an array of coordinates in the same order, such that `[i, j, k]` becomes `((((), i), j), k)`.
+        ///
+        /// Fails to compile if `R` is not `Self::RANK`.
+        fn from_array<const R: usize>(a: [usize; R]) -> Self {
+            const {
+                assert!(
+                    R == Self::RANK,
+                    "the array length must be the number of coordinates"
+                )
+            };
+            Self::read(&a)
         }
+
+        /// Convert to an array of coordinates in the outer-most first order, such that `((((), i), j), k)` becomes `[k, j, i]`.
+        ///
+        /// This is the order of direct indexing, where the cell `[k, j, i]` is `a[k][j][i]`.
+        ///
+        /// Fails to compile if `R` is not `Self::RANK`.
+        fn to_outer_first<const R: usize>(self) -> [usize; R] {
+            let mut a = self.to_array();
+            a.reverse();
+            a
+        }
+
+        /// Convert from an array of coordinates in the outer-most first order, such that `[k, j, i]` becomes `((((), i), j), k)`.
+        ///
+        /// Fails to compile if `R` is not `Self::RANK`.
+        fn from_outer_first<const R: usize>(mut a: [usize; R]) -> Self {
+            a.reverse();
+            Self::from_array(a)
+        }
+
+        #[doc(hidden)]
+        fn write(self, a: &mut [usize]);
+        #[doc(hidden)]
+        fn read(a: &[usize]) -> Self;
+    }
+    impl Sealed for () {}
+    impl Coords for () {
+        const RANK: usize = 0;
+        fn write(self, _: &mut [usize]) {}
+        fn read(_: &[usize]) -> Self {}
     }
-    impl<L: CUList + Reify<usize>, const N: usize> Reify<usize> for Array<L, N> {
-        fn reify() -> usize {
-            1 + L::reify()
+    impl<C: Coords> Sealed for (C, usize) {}
+    impl<C: Coords> Coords for (C, usize) {
+        const RANK: usize = C::RANK + 1;
+        fn write(self, a: &mut [usize]) {
+            self.0.write(a);
+            a[C::RANK] = self.1;
+        }
+        fn read(a: &[usize]) -> Self {
+            (C::read(a), a[C::RANK])
         }
     }
 
+    /// Convert the impl type to a value of type `T`.
+    pub trait Reify<T> {
+        fn reify() -> T;
+    }
+
     impl Reify<CoordType<Value>> for Value {
-        fn reify() -> CoordType<Value> {
-            ()
-        }
+        fn reify() -> CoordType<Value> {}
     }
     impl<L: CUList + Reify<CoordType<L>>, const N: usize> Reify<CoordType<Array<L, N>>>
         for Array<L, N>
@@ -71,36 +279,85 @@ mod private {
         }
     }
 
-    /// Product for recursive types
+    /// Product for recursive types, which is `CUList::LEN` for the type-level lists.
     pub trait Product<T> {
         fn product() -> T;
     }
-    impl Product<usize> for Value {
+    impl<L: CUList> Product<usize> for L {
         fn product() -> usize {
-            1
-        }
-    }
-    impl<L: CUList + Product<usize>, const N: usize> Product<usize> for Array<L, N> {
-        fn product() -> usize {
-            N * L::product()
+            L::LEN
         }
     }
 
+    /// Conversions between flat indices and coordinates.
     pub trait IndexCoord<L: CUList> {
         fn coords(i: usize) -> CoordType<L>;
+        fn index(c: CoordType<L>) -> usize;
+        fn contains(c: CoordType<L>) -> bool;
+        /// Advance the coordinates to the next cell in memory order like an odometer, returning `true` when they wrap around
+        /// to the first cell.
+        fn step(c: &mut CoordType<L>) -> bool;
     }
     impl IndexCoord<Value> for Value {
-        fn coords(_: usize) -> CoordType<Value> {
-            ()
+        fn coords(_: usize) -> CoordType<Value> {}
+        fn step(_: &mut CoordType<Value>) -> bool {
+            true
+        }
+        fn index(_: CoordType<Value>) -> usize {
+            0
+        }
+        fn contains(_: CoordType<Value>) -> bool {
+            true
         }
     }
     impl<L: CUList + IndexCoord<L> + Product<usize>, const N: usize> IndexCoord<Array<L, N>>
         for Array<L, N>
     {
         fn coords(i: usize) -> CoordType<Array<L, N>> {
+            // Empty arrays only have the first coordinates, which `in_order` still computes.
             let prod = L::product();
+            (
+                L::coords(i.checked_rem(prod).unwrap_or(0)),
+                i.checked_div(prod).unwrap_or(0),
+            )
+        }
+        fn index((c, i): CoordType<Array<L, N>>) -> usize {
+            L::index(c) + i * L::product()
+        }
+        fn contains((c, i): CoordType<Array<L, N>>) -> bool {
+            i < N && L::contains(c)
+        }
+        fn step((c, i): &mut CoordType<Array<L, N>>) -> bool {
+            if !L::step(c) {
+                return false;
+            }
+            *i += 1;
+            if *i == N {
+                *i = 0;
+                true
+            } else {
+                false
+            }
+        }
+    }
 
-            (L::coords(i % prod), i / prod)
+    /// Turn a function of the coordinates into a function of the flat index that must be called for each index from `start` in
+    /// memory order, as done by the initialization core, so that the coordinates are advanced incrementally with
+    /// `IndexCoord::step` instead of being computed from the index with a division per array nesting. The index given to the
+    /// returned function is relative to `start`.
+    pub fn in_order<E, L: CUList + IndexCoord<L>>(
+        start: usize,
+        mut f: impl FnMut(CoordType<L>) -> E,
+    ) -> impl FnMut(usize) -> E {
+        let mut c = L::coords(start);
+        move |i| {
+            debug_assert!(
+                L::index(c) == start + i,
+                "cells must be initialized in memory order"
+            );
+            let e = f(c);
+            L::step(&mut c);
+            e
         }
     }
 
@@ -108,29 +365,35 @@ mod private {
     pub trait Arrays<E, L: CUList> {}
     impl<E> Arrays<E, Value> for E {}
     impl<E, L: CUList, A: Arrays<E, L>, const N: usize> Arrays<E, Array<L, N>> for [A; N] {}
-
-    pub trait Initialize<E, L: CUList> {
-        fn initialize(s: &Self, i: usize) -> E;
-    }
-    impl<E: Clone> Initialize<E, Value> for E {
-        fn initialize(e: &Self, _: usize) -> E {
-            e.clone()
-        }
-    }
-    impl<
-            E,
-            L: CUList + IndexCoord<Array<L, N>>,
-            F: Fn(CoordType<Array<L, N>>) -> E,
-            const N: usize,
-        > Initialize<E, Array<L, N>> for F
-    {
-        fn initialize(f: &Self, i: usize) -> E {
-            f(L::coords(i))
-        }
-    }
 }
+pub use aligned::{boxarray_aligned, boxarray_aligned_, AlignedBox};
+#[cfg(any(feature = "allocator_api", feature = "allocator-api2"))]
+pub use allocator::{boxarray_in, boxarray_in_};
+#[cfg(feature = "allocator-api2")]
+pub use allocator_api2;
+pub use builder::{boxarray_uninit, Builder, FinishError};
+pub use bytes::{boxarray_copy, boxarray_zeroed, try_boxarray_zeroed, NoUninit, Zeroable};
+pub use flat::{
+    as_flat, as_flat_mut, into_flat, reshape, reshape_mut, reshape_ref, try_from_boxed_slice,
+    try_from_vec,
+};
+pub use init::{AllocError, FromIterError, InitError};
+#[cfg(feature = "rayon")]
+pub use par::{par_boxarray, par_boxarray_};
 use private::*;
-pub use private::{Array, Value};
+pub use private::{Array, Coords, Value};
+#[cfg(feature = "rand")]
+pub use rand_distr;
+#[cfg(all(feature = "rand", feature = "rayon"))]
+pub use random::par_boxarray_random;
+#[cfg(feature = "rand")]
+pub use random::{boxarray_normal, boxarray_random, boxarray_random_threaded, boxarray_uniform};
+pub use rank::{Rank, Ranked};
+pub use shape::Shape;
+pub use shared::{arcarray, arcarray_, rcarray, rcarray_};
+pub use slice::{boxslice, boxslice_};
+pub use threaded::{boxarray_threaded, boxarray_threaded_};
+pub use vec::BoxArrayVec;
 
 /// The `boxarray` function allow to allocate nested arrays directly on the heap inside a `Box` and initialize it with a constant value of type `E`.
 ///
@@ -160,6 +423,30 @@ pub use private::{Array, Value};
 /// }
 /// ```
 ///
+/// Zero-length arrays and zero-sized elements do not allocate, but the value is still cloned once per cell.
+/// ```
+/// let a: Box<[[u8; 0]; 4]> = boxarray::boxarray(1);
+/// assert_eq!(*a, [[]; 4]);
+///
+/// #[derive(Debug, PartialEq)]
+/// struct Unit;
+/// static CLONES: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+/// impl Clone for Unit {
+///     fn clone(&self) -> Self {
+///         CLONES.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+///         Unit
+///     }
+/// }
+/// let _: Box<[[Unit; 3]; 2]> = boxarray::boxarray(Unit);
+/// assert_eq!(CLONES.load(std::sync::atomic::Ordering::Relaxed), 6);
+/// ```
+///
+/// Elements that own heap memory are cloned into place.
+/// ```
+/// let a: Box<[[String; 3]; 2]> = boxarray::boxarray("cell".to_string());
+/// assert_eq!(a[1][2], "cell");
+/// ```
+///
 /// If the type of the value to initialize with does not correspond, a compiler will be raised.
 /// ```compile_fail
 /// fn nested_array_wrong_type() {
@@ -174,18 +461,8 @@ pub use private::{Array, Value};
 /// }
 /// ```
 ///
-pub fn boxarray<E: Clone, L: CUList, A: Arrays<E, L>>(e: E) -> Box<A> {
-    unsafe {
-        let ptr = alloc_zeroed(Layout::new::<A>());
-        let st = std::mem::size_of::<A>();
-        let se = std::mem::size_of::<E>();
-        let n = st / se;
-        let arr: *mut E = transmute(ptr);
-        for i in 0..n {
-            *arr.add(i) = e.clone();
-        }
-        Box::from_raw(std::mem::transmute(ptr))
-    }
+pub fn boxarray<E: Clone, L: CUList + Product<usize>, A: Arrays<E, L>>(e: E) -> Box<A> {
+    unsafe { init::init(L::product(), |_| e.clone()) }
 }
 
 /// Same as `boxarray` but use a fonction that takes nested tuples of `usize` as coordinates and return a value of type `E` to initialize every cells.
@@ -224,6 +501,44 @@ pub fn boxarray<E: Clone, L: CUList, A: Arrays<E, L>>(e: E) -> Box<A> {
 /// }
 /// ```
 ///
+/// Zero-sized elements still get the function called once per cell.
+/// ```
+/// use std::cell::Cell;
+///
+/// let calls = Cell::new(0);
+/// let _: Box<[[(); 3]; 5]> = boxarray::boxarray_(|(((), _i), _j)| calls.set(calls.get() + 1));
+/// assert_eq!(calls.get(), 15);
+/// let a: Box<[[u64; 3]; 0]> = boxarray::boxarray_(|(((), i), _j)| i as u64);
+/// assert_eq!(a.len(), 0);
+/// let a: Box<[[u64; 0]; 3]> = boxarray::boxarray_(|(((), i), _j)| i as u64);
+/// assert_eq!(*a, [[], [], []]);
+/// ```
+///
+/// If the function panics, the cells already initialized are dropped and the allocation is freed.
+/// ```
+/// use std::{cell::Cell, panic::{catch_unwind, AssertUnwindSafe}, rc::Rc};
+///
+/// struct Counted(Rc<Cell<usize>>);
+/// impl Drop for Counted {
+///     fn drop(&mut self) {
+///         self.0.set(self.0.get() - 1);
+///     }
+/// }
+///
+/// let alive = Rc::new(Cell::new(0));
+/// let res = catch_unwind(AssertUnwindSafe(|| {
+///     let _: Box<[[Counted; 4]; 2]> = boxarray::boxarray_(|(((), i), j)| {
+///         if (i, j) == (1, 1) {
+///             panic!("cannot initialize cell ({i}, {j})");
+///         }
+///         alive.set(alive.get() + 1);
+///         Counted(alive.clone())
+///     });
+/// }));
+/// assert!(res.is_err());
+/// assert_eq!(alive.get(), 0);
+/// ```
+///
 /// Fails to compile when the number of coordinates are not the same as the dimension of the nested arrays.
 /// ```compile_fail
 /// fn nested_array() {
@@ -236,18 +551,330 @@ pub fn boxarray<E: Clone, L: CUList, A: Arrays<E, L>>(e: E) -> Box<A> {
 /// }
 /// ```
 ///
-pub fn boxarray_<E, L: CUList + IndexCoord<L>, A: Arrays<E, L>, F: Fn(CoordType<L>) -> E>(
+pub fn boxarray_<
+    E,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: Fn(CoordType<L>) -> E,
+>(
     f: F,
 ) -> Box<A> {
-    unsafe {
-        let ptr = alloc_zeroed(Layout::new::<A>());
-        let st = std::mem::size_of::<A>();
-        let se = std::mem::size_of::<E>();
-        let n = st / se;
-        let arr: *mut E = transmute(ptr);
-        for i in 0..n {
-            *arr.add(i) = f(L::coords(i));
-        }
-        Box::from_raw(std::mem::transmute(ptr))
+    unsafe { init::init(L::product(), in_order::<E, L>(0, f)) }
+}
+
+/// Same as `boxarray` but return an `AllocError` holding the requested `Layout` if the allocation fails instead of calling `handle_alloc_error`.
+///
+/// # Examples
+///
+/// ```
+/// let a: Box<[[u32; 3]; 2]> = boxarray::try_boxarray(1).unwrap();
+/// assert_eq!(*a, [[1u32; 3]; 2]);
+/// ```
+///
+/// Huge arrays report the layout that could not be allocated.
+/// ```
+/// type Grid = [[[u8; 1 << 20]; 1 << 20]; 1 << 20];
+/// let err = boxarray::try_boxarray::<u8, _, Grid>(0).unwrap_err();
+/// assert_eq!(err.layout(), std::alloc::Layout::new::<Grid>());
+/// ```
+///
+pub fn try_boxarray<E: Clone, L: CUList + Product<usize>, A: Arrays<E, L>>(
+    e: E,
+) -> Result<Box<A>, AllocError> {
+    unsafe { init::try_init(L::product(), |_| e.clone()) }
+}
+
+/// Same as `boxarray_` but return an `AllocError` holding the requested `Layout` if the allocation fails instead of calling `handle_alloc_error`.
+///
+/// # Examples
+///
+/// ```
+/// let a: Box<[[u32; 3]; 2]> = boxarray::try_boxarray_(|(((), i), j)| (i + 3 * j) as u32).unwrap();
+/// assert_eq!(*a, [[0, 1, 2], [3, 4, 5]]);
+/// ```
+///
+/// The function is never called when the allocation fails.
+/// ```
+/// type Grid = [[[u8; 1 << 20]; 1 << 20]; 1 << 20];
+/// let res: Result<Box<Grid>, _> = boxarray::try_boxarray_(|_| -> u8 { unreachable!() });
+/// assert!(res.is_err());
+/// ```
+///
+pub fn try_boxarray_<
+    E,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: Fn(CoordType<L>) -> E,
+>(
+    f: F,
+) -> Result<Box<A>, AllocError> {
+    unsafe { init::try_init(L::product(), in_order::<E, L>(0, f)) }
+}
+
+/// Same as `boxarray_` but use a fallible function returning `Result<E, Err>`.
+///
+/// The initialization stops at the first error: the cells already initialized are dropped, the allocation is freed and the
+/// error is returned in an `InitError` together with the coordinates of the failing cell.
+///
+/// # Examples
+///
+/// ```
+/// let a: Result<Box<[[u8; 3]; 2]>, boxarray::InitError<_, std::num::TryFromIntError>> =
+///     boxarray::try_boxarray_with(|(((), i), j)| u8::try_from(i + 3 * j));
+/// assert_eq!(*a.unwrap(), [[0, 1, 2], [3, 4, 5]]);
+/// ```
+///
+/// The coordinates of the first failing cell are reported.
+/// ```
+/// let table = [[Some("a"), Some("b")], [Some("c"), None]];
+/// let a: Result<Box<[[String; 2]; 2]>, _> =
+///     boxarray::try_boxarray_with(|(((), i), j): (((), usize), usize)| {
+///         table[j][i].map(String::from).ok_or("missing")
+///     });
+/// let err = a.unwrap_err();
+/// assert_eq!(*err.coord(), (((), 1), 1));
+/// assert_eq!(*err.error(), "missing");
+/// ```
+///
+pub fn try_boxarray_with<
+    E,
+    Err,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: Fn(CoordType<L>) -> Result<E, Err>,
+>(
+    f: F,
+) -> Result<Box<A>, InitError<CoordType<L>, Err>> {
+    unsafe { init::init_with(L::product(), in_order::<Result<E, Err>, L>(0, f)) }
+        .map_err(|(i, err)| InitError::new(L::coords(i), err))
+}
+
+/// Same as `boxarray_` but accept a `FnMut`, so the function can hold state such as a counter or a random number generator.
+///
+/// The function is called exactly once per cell, in memory order (the first coordinate varies the fastest).
+///
+/// # Examples
+///
+/// ```
+/// let mut count = 0;
+/// let a: Box<[[u32; 3]; 2]> = boxarray::boxarray_from_fn_mut(|(((), _i), _j)| {
+///     count += 1;
+///     count
+/// });
+/// assert_eq!(*a, [[1, 2, 3], [4, 5, 6]]);
+/// ```
+///
+/// The coordinates are advanced incrementally and match the ones computed from the flat index by `Shape::coords`.
+/// ```
+/// use boxarray::Shape;
+///
+/// type Grid = [[[[usize; 3]; 1]; 4]; 2];
+/// let mut i = 0;
+/// let a: Box<Grid> = boxarray::boxarray_from_fn_mut(|c| {
+///     assert_eq!(c, <Grid as Shape<usize>>::coords(i));
+///     i += 1;
+///     i - 1
+/// });
+/// assert_eq!(boxarray::as_flat::<usize, _, _>(&*a), (0..24).collect::<Vec<_>>());
+/// ```
+///
+/// The coordinates are visited in the same order as the memory layout.
+/// ```
+/// let mut visited = vec![];
+/// let _: Box<[[(); 2]; 2]> = boxarray::boxarray_from_fn_mut(|(((), i), j)| visited.push((i, j)));
+/// assert_eq!(visited, [(0, 0), (1, 0), (0, 1), (1, 1)]);
+/// ```
+///
+pub fn boxarray_from_fn_mut<
+    E,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: FnMut(CoordType<L>) -> E,
+>(
+    f: F,
+) -> Box<A> {
+    unsafe { init::init(L::product(), in_order::<E, L>(0, f)) }
+}
+
+/// Allocate nested arrays on the heap and initialize the cells in memory order with the items of an iterator.
+///
+/// The iterator must yield exactly one item per cell. Otherwise, the cells already initialized are dropped, the allocation is
+/// freed and a `FromIterError` tells how many items were missing or that there were too many.
+///
+/// # Examples
+///
+/// ```
+/// let a: Result<Box<[[char; 3]; 2]>, _> = boxarray::boxarray_from_iter("abcdef".chars());
+/// assert_eq!(*a.unwrap(), [['a', 'b', 'c'], ['d', 'e', 'f']]);
+/// ```
+///
+/// The length of the iterator is checked.
+/// ```
+/// use boxarray::FromIterError;
+///
+/// let a: Result<Box<[[String; 3]; 2]>, _> = boxarray::boxarray_from_iter((0..4).map(|i| i.to_string()));
+/// assert_eq!(a.unwrap_err(), FromIterError::TooFew { missing: 2 });
+/// let a: Result<Box<[[u8; 3]; 2]>, _> = boxarray::boxarray_from_iter(0..7);
+/// assert_eq!(a.unwrap_err(), FromIterError::TooMany);
+/// ```
+///
+pub fn boxarray_from_iter<
+    E,
+    L: CUList + Product<usize>,
+    A: Arrays<E, L>,
+    I: IntoIterator<Item = E>,
+>(
+    iter: I,
+) -> Result<Box<A>, FromIterError> {
+    let n = L::product();
+    let mut iter = iter.into_iter();
+    let a = unsafe { init::init_with(n, |_| iter.next().ok_or(())) }
+        .map_err(|(i, ())| FromIterError::TooFew { missing: n - i })?;
+    match iter.next() {
+        Some(_) => Err(FromIterError::TooMany),
+        None => Ok(a),
     }
 }
+
+/// Same as `boxarray_` but the function takes the coordinates as an array `[i, j, k, ...]`, in the same order as the nested tuples
+/// (the first coordinate indexes the inner-most array).
+///
+/// # Examples
+///
+/// ```
+/// let a: Box<[[[u32; 3]; 2]; 4]> = boxarray::boxarray_idx(|[i, j, k]| (i + j * k) as u32);
+/// let b: Box<[[[u32; 3]; 2]; 4]> = boxarray::boxarray_(|((((), i), j), k)| (i + j * k) as u32);
+/// assert_eq!(a, b);
+/// ```
+///
+/// Code generic over the number of dimensions can use the coordinates as a slice.
+/// ```
+/// fn sum<const R: usize>(c: [usize; R]) -> usize {
+///     c.iter().sum()
+/// }
+/// let a: Box<[[u64; 3]; 2]> = boxarray::boxarray_idx(|c: [usize; 2]| sum(c) as u64);
+/// assert_eq!(*a, [[0, 1, 2], [1, 2, 3]]);
+/// ```
+///
+/// Fails to compile when the number of coordinates is not the same as the dimension of the nested arrays.
+/// ```compile_fail
+/// let a: Box<[[[i32; 3]; 2]; 4]> = boxarray::boxarray_idx(|[i, j]| (i + j) as i32);
+/// ```
+///
+pub fn boxarray_idx<
+    E,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: Fn([usize; R]) -> E,
+    const R: usize,
+>(
+    f: F,
+) -> Box<A> {
+    boxarray_(|c: CoordType<L>| f(c.to_array()))
+}
+
+/// Same as `try_boxarray_` but the function takes the coordinates as an array, see `boxarray_idx`.
+///
+/// # Examples
+///
+/// ```
+/// let a: Result<Box<[[usize; 3]; 2]>, _> = boxarray::try_boxarray_idx(|[i, j]| i + 3 * j);
+/// assert_eq!(*a.unwrap(), [[0, 1, 2], [3, 4, 5]]);
+/// ```
+///
+pub fn try_boxarray_idx<
+    E,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: Fn([usize; R]) -> E,
+    const R: usize,
+>(
+    f: F,
+) -> Result<Box<A>, AllocError> {
+    try_boxarray_(|c: CoordType<L>| f(c.to_array()))
+}
+
+/// Same as `try_boxarray_with` but the function takes the coordinates as an array, see `boxarray_idx`.
+///
+/// The coordinates of the failing cell are also reported as an array.
+///
+/// # Examples
+///
+/// ```
+/// let a: Result<Box<[[u8; 16]; 32]>, _> = boxarray::try_boxarray_idx_with(|[i, j]| u8::try_from(i * j));
+/// assert_eq!(*a.unwrap_err().coord(), [15, 18]);
+/// ```
+///
+pub fn try_boxarray_idx_with<
+    E,
+    Err,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: Fn([usize; R]) -> Result<E, Err>,
+    const R: usize,
+>(
+    f: F,
+) -> Result<Box<A>, InitError<[usize; R], Err>> {
+    try_boxarray_with::<E, Err, L, A, _>(|c| f(c.to_array())).map_err(|e| {
+        let (c, err) = e.into_parts();
+        InitError::new(c.to_array(), err)
+    })
+}
+
+/// Same as `boxarray_from_fn_mut` but the function takes the coordinates as an array, see `boxarray_idx`.
+///
+/// # Examples
+///
+/// ```
+/// let mut visited = vec![];
+/// let _: Box<[[(); 2]; 2]> = boxarray::boxarray_idx_from_fn_mut(|c| visited.push(c));
+/// assert_eq!(visited, [[0, 0], [1, 0], [0, 1], [1, 1]]);
+/// ```
+///
+pub fn boxarray_idx_from_fn_mut<
+    E,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: FnMut([usize; R]) -> E,
+    const R: usize,
+>(
+    mut f: F,
+) -> Box<A> {
+    boxarray_from_fn_mut(|c: CoordType<L>| f(c.to_array()))
+}
+
+/// Same as `boxarray_idx` but the coordinates are given in the outer-most first order, such that the function called with
+/// `[k, j, i]` initializes the cell `a[k][j][i]`.
+///
+/// # Examples
+///
+/// ```
+/// let a: Box<[[[usize; 3]; 2]; 4]> = boxarray::boxarray_outer_first(|[k, j, i]| 100 * k + 10 * j + i);
+/// for k in 0..4 {
+///     for j in 0..2 {
+///         for i in 0..3 {
+///             assert_eq!(a[k][j][i], 100 * k + 10 * j + i);
+///         }
+///     }
+/// }
+/// ```
+///
+/// The default order of `boxarray_idx` lists the inner-most index first instead.
+/// ```
+/// let a: Box<[[usize; 3]; 2]> = boxarray::boxarray_outer_first(|[j, i]| 10 * j + i);
+/// let b: Box<[[usize; 3]; 2]> = boxarray::boxarray_idx(|[i, j]| 10 * j + i);
+/// assert_eq!(a, b);
+/// assert_eq!(a[1][2], 12);
+/// ```
+///
+pub fn boxarray_outer_first<
+    E,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: Fn([usize; R]) -> E,
+    const R: usize,
+>(
+    f: F,
+) -> Box<A> {
+    boxarray_(|c: CoordType<L>| f(c.to_outer_first()))
+}
@@ -0,0 +1,73 @@
+//! Parallel initialization with rayon.
+use crate::init;
+use crate::private::*;
+use rayon::prelude::*;
+
+/// Number of chunks given to each rayon thread, so that the work is balanced when some chunks are slower to initialize.
+const CHUNKS_PER_THREAD: usize = 4;
+
+/// Initialize `n` values in parallel, splitting them into a few chunks per rayon thread.
+pub(crate) unsafe fn par_init<E: Send, A>(
+    n: usize,
+    fill_chunk: impl Fn(usize, *mut E, usize) + Sync,
+) -> Box<A> {
+    let chunk = n.div_ceil(rayon::current_num_threads() * CHUNKS_PER_THREAD);
+    init::init_chunks(
+        n,
+        chunk,
+        |count, run| (0..count).into_par_iter().for_each(run),
+        fill_chunk,
+    )
+}
+
+/// Same as `boxarray` but initialize the cells in parallel with rayon.
+///
+/// The result is the same as with `boxarray`. If cloning panics, the cells already initialized are dropped and the allocation
+/// is freed before the panic is propagated.
+///
+/// # Examples
+///
+/// ```
+/// let a: Box<[[[f64; 100]; 100]; 100]> = boxarray::par_boxarray(7.0);
+/// assert!(boxarray::as_flat::<f64, _, _>(&*a).iter().all(|x| *x == 7.0));
+///
+/// let a: Box<[[String; 3]; 2]> = boxarray::par_boxarray("cell".to_string());
+/// assert_eq!(a, boxarray::boxarray("cell".to_string()));
+/// ```
+pub fn par_boxarray<E: Clone + Send + Sync, L: CUList + Product<usize>, A: Arrays<E, L>>(
+    e: E,
+) -> Box<A> {
+    unsafe {
+        par_init(L::product(), |_, ptr, len| {
+            init::fill(ptr, len, |_| e.clone())
+        })
+    }
+}
+
+/// Same as `boxarray_` but initialize the cells in parallel with rayon.
+///
+/// The function is called exactly once per cell, so the result is the same as with `boxarray_`. If the function panics, the
+/// cells already initialized are dropped and the allocation is freed before the panic is propagated.
+///
+/// # Examples
+///
+/// ```
+/// let f = |((((), i), j), k)| (i + 100 * j + 10_000 * k) as u64;
+/// let a: Box<[[[u64; 100]; 100]; 100]> = boxarray::par_boxarray_(f);
+/// let b: Box<[[[u64; 100]; 100]; 100]> = boxarray::boxarray_(f);
+/// assert_eq!(a, b);
+/// ```
+pub fn par_boxarray_<
+    E: Send,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: Fn(CoordType<L>) -> E + Sync,
+>(
+    f: F,
+) -> Box<A> {
+    unsafe {
+        par_init(L::product(), |start, ptr, len| {
+            init::fill(ptr, len, in_order::<E, L>(start, &f))
+        })
+    }
+}
@@ -0,0 +1,220 @@
+//! Deterministic random initialization, identical whatever the number of threads.
+use crate::init;
+use crate::private::*;
+use rand_chacha::rand_core::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use rand_distr::num_traits::Float;
+use rand_distr::uniform::SampleUniform;
+use rand_distr::{Distribution, Normal, StandardNormal, Uniform};
+
+/// Key of the ChaCha8 generator expanded from the master seed, shared by all the cells.
+fn key(seed: u64) -> <ChaCha8Rng as SeedableRng>::Seed {
+    ChaCha8Rng::seed_from_u64(seed).get_seed()
+}
+
+/// Number of consecutive cells sampled from the same stream of the generator, independently of the number of threads.
+const BLOCK: usize = 1024;
+
+/// Generator of the block of cells of index `b`, which is the stream `b` of the ChaCha8 generator of the master seed.
+fn block_rng(key: <ChaCha8Rng as SeedableRng>::Seed, b: usize) -> ChaCha8Rng {
+    let mut rng = ChaCha8Rng::from_seed(key);
+    rng.set_stream(b as u64);
+    rng
+}
+
+/// Sampler of the cells from the flat index `start`, called with the offset of each cell from `start` in order. It
+/// fast-forwards from the start of the block of `start`, so that a cell gets the same value wherever the chunk it belongs
+/// to starts.
+fn sampler<'a, E, D: Distribution<E>>(
+    key: <ChaCha8Rng as SeedableRng>::Seed,
+    dist: &'a D,
+    start: usize,
+) -> impl FnMut(usize) -> E + 'a {
+    let mut rng = block_rng(key, start / BLOCK);
+    for _ in 0..start % BLOCK {
+        let _ = dist.sample(&mut rng);
+    }
+    move |i| {
+        let i = start + i;
+        if i.is_multiple_of(BLOCK) && i != start {
+            rng = block_rng(key, i / BLOCK);
+        }
+        dist.sample(&mut rng)
+    }
+}
+
+/// Fill `len` cells starting at the flat index `start` with samples of `dist`.
+///
+/// # Safety
+///
+/// `ptr` must be valid for writes of `len` consecutive values of type `E`.
+unsafe fn fill_random<E, D: Distribution<E>>(
+    key: <ChaCha8Rng as SeedableRng>::Seed,
+    dist: &D,
+    start: usize,
+    ptr: *mut E,
+    len: usize,
+) {
+    init::fill(ptr, len, sampler(key, dist, start))
+}
+
+/// Initialize nested arrays with random samples of `dist`.
+///
+/// The cells are split in blocks of 1024 consecutive cells in memory order (see `Shape::index`), each block being sampled
+/// in order with its own generator: the stream given by the index of the block of the ChaCha8 generator seeded by `seed`.
+/// A cell therefore only depends on `seed`, on `dist` and on its flat index, so `boxarray_random_threaded` and
+/// `par_boxarray_random` give exactly the same arrays whatever the number of threads, and the results are reproducible
+/// across platforms.
+///
+/// The distributions of `rand_distr`, re-exported as `boxarray::rand_distr`, can be used, such as `Uniform`, `Normal` or
+/// `StandardUniform`. See also `boxarray_uniform` and `boxarray_normal`.
+///
+/// # Examples
+///
+/// ```
+/// use boxarray::rand_distr::{StandardUniform, Uniform};
+///
+/// let a: Box<[[[f64; 10]; 10]; 10]> = boxarray::boxarray_random::<f64, _, _, _>(42, StandardUniform);
+/// assert!(boxarray::as_flat::<f64, _, _>(&*a).iter().all(|x| (0.0..1.0).contains(x)));
+///
+/// let dice = Uniform::new_inclusive(1, 6).unwrap();
+/// let a: Box<[[u8; 100]; 100]> = boxarray::boxarray_random(7, dice);
+/// assert_eq!(a, boxarray::boxarray_random(7, dice));
+/// assert_ne!(a, boxarray::boxarray_random(8, dice));
+/// ```
+///
+/// A cell only depends on its flat index, so arrays with another shape but the same number of cells hold the same values.
+/// ```
+/// use boxarray::rand_distr::StandardUniform;
+///
+/// let a: Box<[[u64; 8]; 4]> = boxarray::boxarray_random::<u64, _, _, _>(1, StandardUniform);
+/// let b: Box<[u64; 32]> = boxarray::boxarray_random::<u64, _, _, _>(1, StandardUniform);
+/// assert_eq!(*boxarray::reshape::<u64, _, _, [u64; 32]>(a), *b);
+/// ```
+pub fn boxarray_random<E, L: CUList + Product<usize>, A: Arrays<E, L>, D: Distribution<E>>(
+    seed: u64,
+    dist: D,
+) -> Box<A> {
+    let key = key(seed);
+    unsafe { init::init(L::product(), sampler(key, &dist, 0)) }
+}
+
+/// Same as `boxarray_random` with the values uniformly distributed in `[low, high)`.
+///
+/// # Panics
+///
+/// Panics if `low >= high` or if the range is not finite.
+///
+/// # Examples
+///
+/// ```
+/// let a: Box<[[f32; 100]; 100]> = boxarray::boxarray_uniform(42, -1.0, 1.0);
+/// assert!(boxarray::as_flat::<f32, _, _>(&*a).iter().all(|x| (-1.0..1.0).contains(x)));
+///
+/// let a: Box<[[i32; 100]; 100]> = boxarray::boxarray_uniform(42, -5, 5);
+/// assert!(boxarray::as_flat::<i32, _, _>(&*a).iter().all(|x| (-5..5).contains(x)));
+/// ```
+pub fn boxarray_uniform<E: SampleUniform, L: CUList + Product<usize>, A: Arrays<E, L>>(
+    seed: u64,
+    low: E,
+    high: E,
+) -> Box<A> {
+    let dist = Uniform::new(low, high).expect("invalid uniform range");
+    boxarray_random(seed, dist)
+}
+
+/// Same as `boxarray_random` with the values following a normal distribution of mean `mean` and standard deviation
+/// `std_dev`.
+///
+/// # Panics
+///
+/// Panics if `std_dev` is negative or not finite.
+///
+/// # Examples
+///
+/// ```
+/// let a: Box<[[f64; 1000]; 1000]> = boxarray::boxarray_normal(42, 5.0, 2.0);
+/// let flat: &[f64] = boxarray::as_flat(&*a);
+/// let mean = flat.iter().sum::<f64>() / flat.len() as f64;
+/// let var = flat.iter().map(|x| (x - mean).powi(2)).sum::<f64>() / flat.len() as f64;
+/// assert!((mean - 5.0).abs() < 0.01);
+/// assert!((var.sqrt() - 2.0).abs() < 0.01);
+/// ```
+pub fn boxarray_normal<F: Float, L: CUList + Product<usize>, A: Arrays<F, L>>(
+    seed: u64,
+    mean: F,
+    std_dev: F,
+) -> Box<A>
+where
+    StandardNormal: Distribution<F>,
+{
+    let dist = Normal::new(mean, std_dev).expect("invalid standard deviation");
+    boxarray_random(seed, dist)
+}
+
+/// Same as `boxarray_random` but initialize the cells with `threads` threads, or `std::thread::available_parallelism`
+/// threads if `threads` is 0, as `boxarray_threaded_` does.
+///
+/// The result is the same as with `boxarray_random` whatever the number of threads.
+///
+/// # Examples
+///
+/// ```
+/// use boxarray::rand_distr::Normal;
+///
+/// let dist = Normal::new(0.0, 1.0).unwrap();
+/// let a: Box<[[[f64; 50]; 50]; 50]> = boxarray::boxarray_random(42, dist);
+/// for threads in [1, 3, 8] {
+///     let b: Box<[[[f64; 50]; 50]; 50]> = boxarray::boxarray_random_threaded(threads, 42, dist);
+///     assert_eq!(a, b);
+/// }
+/// ```
+pub fn boxarray_random_threaded<
+    E: Send,
+    L: CUList + Product<usize> + Reify<CoordType<L>>,
+    A: Arrays<E, L>,
+    D: Distribution<E> + Sync,
+>(
+    threads: usize,
+    seed: u64,
+    dist: D,
+) -> Box<A> {
+    let key = key(seed);
+    unsafe {
+        crate::threaded::threaded_init::<E, L, A>(threads, |start, ptr, len| {
+            fill_random(key, &dist, start, ptr, len)
+        })
+    }
+}
+
+/// Same as `boxarray_random` but initialize the cells in parallel with rayon.
+///
+/// The result is the same as with `boxarray_random` whatever the number of threads of rayon.
+///
+/// # Examples
+///
+/// ```
+/// use boxarray::rand_distr::Uniform;
+///
+/// let dist = Uniform::new(0u32, 1000).unwrap();
+/// let a: Box<[[[u32; 50]; 50]; 50]> = boxarray::boxarray_random(42, dist);
+/// let b: Box<[[[u32; 50]; 50]; 50]> = boxarray::par_boxarray_random(42, dist);
+/// assert_eq!(a, b);
+/// ```
+#[cfg(feature = "rayon")]
+pub fn par_boxarray_random<
+    E: Send,
+    L: CUList + Product<usize>,
+    A: Arrays<E, L>,
+    D: Distribution<E> + Sync,
+>(
+    seed: u64,
+    dist: D,
+) -> Box<A> {
+    let key = key(seed);
+    unsafe {
+        crate::par::par_init(L::product(), |start, ptr, len| {
+            fill_random(key, &dist, start, ptr, len)
+        })
+    }
+}
@@ -0,0 +1,153 @@
+//! Explicit selection of the depth at which the elements of nested arrays are taken.
+use crate::private::*;
+use crate::{AllocError, InitError};
+
+/// Nested arrays seen as `R` nested arrays of `Self::Elem`, where `Self::Elem` can itself be an array.
+///
+/// This trait is sealed and implemented for every nested array type up to a rank of 8.
+///
+/// # Examples
+///
+/// ```
+/// use boxarray::Ranked;
+///
+/// fn last<A: Ranked<2>>(a: &A) -> Option<&A::Elem> {
+///     boxarray::as_flat::<A::Elem, A::List, A>(a).last()
+/// }
+/// let a = [[[1, 2, 3], [4, 5, 6]]];
+/// assert_eq!(last(&a), Some(&[4, 5, 6]));
+/// ```
+pub trait Ranked<const R: usize>:
+    RankedSealed<R> + Arrays<<Self as Ranked<R>>::Elem, <Self as Ranked<R>>::List>
+{
+    /// Type of the cells.
+    type Elem;
+    /// Type-level list of the `R` dimensions, as used by the `boxarray` functions.
+    type List: CUList + IndexCoord<Self::List> + Product<usize>;
+}
+
+macro_rules! nested {
+    ($t:ty;) => { $t };
+    ($t:ty; $n:ident $($ns:ident)*) => { nested!([$t; $n]; $($ns)*) };
+}
+
+macro_rules! nested_list {
+    ($l:ty;) => { $l };
+    ($l:ty; $n:ident $($ns:ident)*) => { nested_list!(Array<$l, $n>; $($ns)*) };
+}
+pub(crate) use {nested, nested_list};
+
+macro_rules! ranked {
+    ($($r:literal: $($n:ident)*;)*) => {
+        $(
+            impl<T, $(const $n: usize),*> RankedSealed<$r> for nested!(T; $($n)*) {}
+            impl<T, $(const $n: usize),*> Ranked<$r> for nested!(T; $($n)*) {
+                type Elem = T;
+                type List = nested_list!(Value; $($n)*);
+            }
+        )*
+    };
+}
+
+ranked! {
+    0: ;
+    1: N0;
+    2: N0 N1;
+    3: N0 N1 N2;
+    4: N0 N1 N2 N3;
+    5: N0 N1 N2 N3 N4;
+    6: N0 N1 N2 N3 N4 N5;
+    7: N0 N1 N2 N3 N4 N5 N6;
+    8: N0 N1 N2 N3 N4 N5 N6 N7;
+}
+
+/// Marker fixing the number `R` of array nesting to consider, the cells being whatever type is nested deeper.
+///
+/// The `boxarray` functions find the element type from the value or the function given to them, which can be ambiguous when
+/// the element type is itself an array, or fail to infer the type of the coordinates in the function. `Rank::<R>` provides the
+/// same functions with the rank fixed, so that the element type and the coordinates are known from the type of the result.
+///
+/// The element type can also be fixed with the first generic parameter of the `boxarray` functions, such as
+/// `boxarray::boxarray_::<[f64; 3], _, _, _>(f)`.
+///
+/// # Examples
+///
+/// A field of 3-vectors on a 100 by 100 grid.
+/// ```
+/// use boxarray::Rank;
+///
+/// let a: Box<[[[f64; 3]; 100]; 100]> = Rank::<2>::boxarray_(|(((), i), j)| [i as f64, j as f64, 0.0]);
+/// assert_eq!(a[20][10], [10.0, 20.0, 0.0]);
+///
+/// let b: Box<[[[f64; 3]; 100]; 100]> = Rank::<2>::boxarray_idx(|[i, j]| [i as f64, j as f64, 0.0]);
+/// assert_eq!(a, b);
+/// ```
+///
+/// The coordinates are known to be `usize`, so they can be used without annotations.
+/// ```
+/// use boxarray::Rank;
+///
+/// let a: Box<[[usize; 3]; 2]> = Rank::<2>::boxarray_(|(((), i), j)| i + 3 * j);
+/// assert_eq!(*a, [[0, 1, 2], [3, 4, 5]]);
+/// ```
+///
+/// Fails to compile when the function does not take `R` coordinates.
+/// ```compile_fail
+/// use boxarray::Rank;
+///
+/// let a: Box<[[[f64; 3]; 100]; 100]> = Rank::<2>::boxarray_(|((((), i), j), k)| 0.0);
+/// ```
+pub struct Rank<const R: usize>;
+
+impl<const R: usize> Rank<R> {
+    /// Same as `boxarray::boxarray` with the element type taken at rank `R`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let a: Box<[[[u8; 2]; 3]; 4]> = boxarray::Rank::<2>::boxarray([1, 2]);
+    /// assert_eq!(a[3][2], [1, 2]);
+    /// ```
+    pub fn boxarray<A: Ranked<R>>(e: A::Elem) -> Box<A>
+    where
+        A::Elem: Clone,
+    {
+        crate::boxarray::<A::Elem, A::List, A>(e)
+    }
+
+    /// Same as `boxarray::boxarray_` with the element type taken at rank `R`.
+    pub fn boxarray_<A: Ranked<R>, F: Fn(CoordType<A::List>) -> A::Elem>(f: F) -> Box<A> {
+        crate::boxarray_::<A::Elem, A::List, A, F>(f)
+    }
+
+    /// Same as `boxarray::boxarray_idx` with the element type taken at rank `R`.
+    pub fn boxarray_idx<A: Ranked<R>, F: Fn([usize; R]) -> A::Elem>(f: F) -> Box<A> {
+        crate::boxarray_idx::<A::Elem, A::List, A, F, R>(f)
+    }
+
+    /// Same as `boxarray::try_boxarray_` with the element type taken at rank `R`.
+    pub fn try_boxarray_<A: Ranked<R>, F: Fn(CoordType<A::List>) -> A::Elem>(
+        f: F,
+    ) -> Result<Box<A>, AllocError> {
+        crate::try_boxarray_::<A::Elem, A::List, A, F>(f)
+    }
+
+    /// Same as `boxarray::try_boxarray_with` with the element type taken at rank `R`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let a: Result<Box<[[[u8; 2]; 3]; 4]>, _> =
+    ///     boxarray::Rank::<2>::try_boxarray_with(|(((), i), j)| Ok::<_, ()>([i as u8, j as u8]));
+    /// assert_eq!(a.unwrap()[3][2], [2, 3]);
+    /// ```
+    pub fn try_boxarray_with<
+        Err,
+        A: Ranked<R>,
+        F: Fn(CoordType<A::List>) -> Result<A::Elem, Err>,
+    >(
+        f: F,
+    ) -> Result<Box<A>, InitError<CoordType<A::List>, Err>> {
+        crate::try_boxarray_with::<A::Elem, Err, A::List, A, F>(f)
+    }
+}
@@ -0,0 +1,176 @@
+//! Public view of the shape of nested arrays.
+use crate::private::*;
+use crate::rank::{nested, nested_list};
+
+/// Shape of nested arrays of elements of type `E`, such as `[[[f64; 3]; 2]; 4]` with `E = f64`.
+///
+/// The element type is given explicitly, as for the `boxarray` functions, since nested arrays can be seen as arrays of
+/// several element types: `[[f64; 3]; 2]` is a `Shape<f64>` of rank 2, a `Shape<[f64; 3]>` of rank 1 and a
+/// `Shape<[[f64; 3]; 2]>` of rank 0. Any type can be the element type.
+///
+/// This trait is sealed and implemented for every nested array type up to a rank of 8, as `Ranked`.
+///
+/// # Examples
+///
+/// ```
+/// use boxarray::Shape;
+///
+/// type Grid = [[[f64; 3]; 2]; 4];
+/// assert_eq!(<Grid as Shape<f64>>::RANK, 3);
+/// assert_eq!(<Grid as Shape<f64>>::LEN, 24);
+/// assert_eq!(<Grid as Shape<f64>>::dims(), [3, 2, 4]);
+/// assert_eq!(<Grid as Shape<[f64; 3]>>::dims(), [2, 4]);
+///
+/// fn cells<E, A: Shape<E>>(_: &A) -> usize {
+///     A::LEN
+/// }
+/// let a: Box<Grid> = boxarray::boxarray(0.0);
+/// assert_eq!(cells::<f64, _>(&*a), 24);
+/// assert_eq!(cells::<[f64; 3], _>(&*a), 8);
+/// ```
+///
+/// Generic code gets the size of each dimension with `write_dims`, as the rank cannot be used as an array length there.
+/// ```
+/// use boxarray::Shape;
+/// use std::num::FpCategory;
+///
+/// fn dims<E, A: Shape<E>>() -> Vec<usize> {
+///     let mut dims = vec![0; A::RANK];
+///     A::write_dims(&mut dims);
+///     dims
+/// }
+/// assert_eq!(dims::<FpCategory, [[FpCategory; 4]; 2]>(), [4, 2]);
+/// assert_eq!(dims::<u8, u8>(), []);
+/// ```
+///
+/// Flat indices in memory order can be converted to and from coordinates, either as nested tuples or as arrays.
+/// ```
+/// use boxarray::Shape;
+///
+/// type Grid = [[[f64; 3]; 2]; 4];
+/// fn index(c: ((((), usize), usize), usize)) -> usize {
+///     <Grid as Shape<f64>>::index(c)
+/// }
+/// assert_eq!(index(((((), 2), 1), 3)), 2 + 3 * (1 + 2 * 3));
+/// assert_eq!(<Grid as Shape<f64>>::coords(23), ((((), 2), 1), 3));
+/// assert_eq!(<Grid as Shape<f64>>::index_idx([1, 0, 2]), 13);
+/// assert_eq!(<Grid as Shape<f64>>::coords_idx(13), [1, 0, 2]);
+/// assert_eq!(<Grid as Shape<f64>>::checked_index_idx([3, 0, 0]), None);
+/// assert_eq!(<Grid as Shape<f64>>::checked_coords_idx::<3>(24), None);
+///
+/// let a: Box<Grid> = boxarray::boxarray_idx(|c: [usize; 3]| <Grid as Shape<f64>>::index_idx(c) as f64);
+/// let flat: &[f64] = boxarray::as_flat(&*a);
+/// for (i, x) in flat.iter().enumerate() {
+///     assert_eq!(*x, i as f64);
+/// }
+/// ```
+///
+/// Generic code can initialize any shape with the type-level list it provides.
+/// ```
+/// use boxarray::Shape;
+///
+/// fn filled<E: Clone, A: Shape<E>>(e: E) -> Box<A> {
+///     boxarray::boxarray::<E, A::List, A>(e)
+/// }
+/// let a: Box<[[u8; 3]; 2]> = filled(1);
+/// assert_eq!(*a, [[1; 3]; 2]);
+/// ```
+pub trait Shape<E>: ShapeSealed<E> + Arrays<E, <Self as Shape<E>>::List> {
+    /// Type-level list of the dimensions, as used by the `boxarray` functions.
+    type List: CUList + IndexCoord<Self::List> + Product<usize> + Reify<CoordType<Self::List>>;
+    /// Number of dimensions.
+    const RANK: usize = <CoordType<Self::List> as Coords>::RANK;
+    /// Number of cells.
+    const LEN: usize = <Self::List as CUList>::LEN;
+
+    /// Size of each dimension, in the same order as the coordinates given by `boxarray_idx` (the inner-most array first).
+    ///
+    /// Fails to compile if `R` is not `Self::RANK`, which cannot be written in generic code, see `write_dims`.
+    fn dims<const R: usize>() -> [usize; R] {
+        Self::List::reify().to_array()
+    }
+
+    /// Write the size of each dimension in the first `Self::RANK` values of the slice, in the same order as `Shape::dims`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `dims` is shorter than `Self::RANK`.
+    fn write_dims(dims: &mut [usize]) {
+        Self::List::reify().write(dims)
+    }
+
+    /// Flat index, in memory order, of the cell at the given coordinates.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are out of bounds.
+    fn index(c: CoordType<Self::List>) -> usize {
+        Self::checked_index(c).expect("coordinates out of bounds")
+    }
+
+    /// Same as `index` but return `None` if the coordinates are out of bounds.
+    fn checked_index(c: CoordType<Self::List>) -> Option<usize> {
+        Self::List::contains(c).then(|| <Self::List as IndexCoord<Self::List>>::index(c))
+    }
+
+    /// Same as `index` but with the coordinates as an array, see `boxarray_idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the coordinates are out of bounds.
+    fn index_idx<const R: usize>(c: [usize; R]) -> usize {
+        Self::index(Coords::from_array(c))
+    }
+
+    /// Same as `index_idx` but return `None` if the coordinates are out of bounds.
+    fn checked_index_idx<const R: usize>(c: [usize; R]) -> Option<usize> {
+        Self::checked_index(Coords::from_array(c))
+    }
+
+    /// Coordinates of the cell at the given flat index, in memory order.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is not less than `Self::LEN`.
+    fn coords(i: usize) -> CoordType<Self::List> {
+        Self::checked_coords(i).expect("index out of bounds")
+    }
+
+    /// Same as `coords` but return `None` if the index is not less than `Self::LEN`.
+    fn checked_coords(i: usize) -> Option<CoordType<Self::List>> {
+        (i < Self::LEN).then(|| <Self::List as IndexCoord<Self::List>>::coords(i))
+    }
+
+    /// Same as `coords` but with the coordinates as an array, see `boxarray_idx`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the index is not less than `Self::LEN`.
+    fn coords_idx<const R: usize>(i: usize) -> [usize; R] {
+        Self::coords(i).to_array()
+    }
+
+    /// Same as `coords_idx` but return `None` if the index is not less than `Self::LEN`.
+    fn checked_coords_idx<const R: usize>(i: usize) -> Option<[usize; R]> {
+        Self::checked_coords(i).map(Coords::to_array)
+    }
+}
+
+macro_rules! shape {
+    ($($n:ident)*) => {
+        impl<T, $(const $n: usize),*> ShapeSealed<T> for nested!(T; $($n)*) {}
+        impl<T, $(const $n: usize),*> Shape<T> for nested!(T; $($n)*) {
+            type List = nested_list!(Value; $($n)*);
+        }
+    };
+}
+
+shape!();
+shape!(N0);
+shape!(N0 N1);
+shape!(N0 N1 N2);
+shape!(N0 N1 N2 N3);
+shape!(N0 N1 N2 N3 N4);
+shape!(N0 N1 N2 N3 N4 N5);
+shape!(N0 N1 N2 N3 N4 N5 N6);
+shape!(N0 N1 N2 N3 N4 N5 N6 N7);
@@ -0,0 +1,119 @@
+//! Initialization directly inside reference-counted allocations.
+use crate::init;
+use crate::private::*;
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Allocate an `Rc<A>` made of `n` values of type `E` and initialize them in place with `f` through the initialization core.
+///
+/// # Safety
+///
+/// `A` must consist of exactly `n` consecutive values of type `E`.
+unsafe fn init_rc<E, A>(n: usize, f: impl FnMut(usize) -> E) -> Rc<A> {
+    let mut uninit = Rc::<A>::new_uninit();
+    let ptr = Rc::get_mut(&mut uninit)
+        .expect("new Rc is unique")
+        .as_mut_ptr();
+    init::fill(ptr as *mut E, n, f);
+    uninit.assume_init()
+}
+
+/// Same as `init_rc` but for an `Arc<A>`.
+///
+/// # Safety
+///
+/// `A` must consist of exactly `n` consecutive values of type `E`.
+unsafe fn init_arc<E, A>(n: usize, f: impl FnMut(usize) -> E) -> Arc<A> {
+    let mut uninit = Arc::<A>::new_uninit();
+    let ptr = Arc::get_mut(&mut uninit)
+        .expect("new Arc is unique")
+        .as_mut_ptr();
+    init::fill(ptr as *mut E, n, f);
+    uninit.assume_init()
+}
+
+/// Same as `boxarray` but return an `Rc`, the cells being written directly inside the reference-counted allocation.
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let a: Rc<[[[f64; 3]; 2]; 4]> = boxarray::rcarray(7.0);
+/// assert_eq!(*a, [[[7.0; 3]; 2]; 4]);
+/// ```
+pub fn rcarray<E: Clone, L: CUList + Product<usize>, A: Arrays<E, L>>(e: E) -> Rc<A> {
+    unsafe { init_rc(L::product(), |_| e.clone()) }
+}
+
+/// Same as `boxarray_` but return an `Rc`, the cells being written directly inside the reference-counted allocation.
+///
+/// If the function panics, the cells already initialized are dropped and the allocation is freed before the panic is
+/// propagated.
+///
+/// # Examples
+///
+/// ```
+/// use std::rc::Rc;
+///
+/// let a: Rc<[[u32; 3]; 2]> = boxarray::rcarray_(|(((), i), j)| (i + 3 * j) as u32);
+/// let b = Rc::clone(&a);
+/// assert_eq!(*b, [[0, 1, 2], [3, 4, 5]]);
+/// ```
+pub fn rcarray_<
+    E,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: Fn(CoordType<L>) -> E,
+>(
+    f: F,
+) -> Rc<A> {
+    unsafe { init_rc(L::product(), in_order::<E, L>(0, f)) }
+}
+
+/// Same as `boxarray` but return an `Arc`, the cells being written directly inside the reference-counted allocation.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+///
+/// let a: Arc<[[f64; 1024]; 1024]> = boxarray::arcarray(0.5);
+/// assert!(a.iter().flatten().all(|x| *x == 0.5));
+/// ```
+pub fn arcarray<E: Clone, L: CUList + Product<usize>, A: Arrays<E, L>>(e: E) -> Arc<A> {
+    unsafe { init_arc(L::product(), |_| e.clone()) }
+}
+
+/// Same as `boxarray_` but return an `Arc`, the cells being written directly inside the reference-counted allocation, so
+/// large lookup tables can be shared between threads without any copy.
+///
+/// If the function panics, the cells already initialized are dropped and the allocation is freed before the panic is
+/// propagated.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let table: Arc<[[f64; 1024]; 1024]> = boxarray::arcarray_(|(((), i), j)| (i * j) as f64);
+/// let handles: Vec<_> = (0..4)
+///     .map(|t| {
+///         let table = Arc::clone(&table);
+///         thread::spawn(move || table[t][1023])
+///     })
+///     .collect();
+/// let res: Vec<f64> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+/// assert_eq!(res, [0.0, 1023.0, 2046.0, 3069.0]);
+/// ```
+pub fn arcarray_<
+    E,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: Fn(CoordType<L>) -> E,
+>(
+    f: F,
+) -> Arc<A> {
+    unsafe { init_arc(L::product(), in_order::<E, L>(0, f)) }
+}
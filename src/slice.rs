@@ -0,0 +1,84 @@
+//! Boxed slices of nested arrays, whose outer-most length is only known at run time.
+use crate::init;
+use crate::private::*;
+
+/// Same as `in_order` for consecutive nested arrays, the first one having the index `first`: the returned function must be
+/// called for each cell of these arrays in memory order, and calls `f` with the coordinates in the current array followed
+/// by its index.
+pub(crate) fn slabs_in_order<E, L: CUList + IndexCoord<L>>(
+    first: usize,
+    mut f: impl FnMut((CoordType<L>, usize)) -> E,
+) -> impl FnMut(usize) -> E {
+    let mut c = L::coords(0);
+    let mut k = first;
+    move |_| {
+        let e = f((c, k));
+        if L::step(&mut c) {
+            k += 1;
+        }
+        e
+    }
+}
+
+/// Allocate `len` nested arrays `A` made of `L::product()` values of type `E` each, and initialize them with `f` through
+/// the initialization core, calling it with the flat index of each cell.
+///
+/// # Safety
+///
+/// `A` must consist of exactly `L::product()` consecutive values of type `E`.
+unsafe fn init_slice<E, L: CUList + Product<usize>, A>(
+    len: usize,
+    f: impl FnMut(usize) -> E,
+) -> Box<[A]> {
+    let n = len.checked_mul(L::product()).expect("capacity overflow");
+    let mut uninit = Box::<[A]>::new_uninit_slice(len);
+    init::fill(uninit.as_mut_ptr() as *mut E, n, f);
+    uninit.assume_init()
+}
+
+/// Same as `boxarray` but allocate a boxed slice of `len` nested arrays, all the cells being initialized to `e`.
+///
+/// # Examples
+///
+/// ```
+/// let frames: Box<[[[f32; 3]; 64]]> = boxarray::boxslice(10, 0.0);
+/// assert_eq!(frames.len(), 10);
+/// assert_eq!(frames[9], [[0.0; 3]; 64]);
+///
+/// let empty: Box<[[[f32; 3]; 64]]> = boxarray::boxslice(0, 0.0);
+/// assert!(empty.is_empty());
+/// ```
+pub fn boxslice<E: Clone, L: CUList + Product<usize>, A: Arrays<E, L>>(
+    len: usize,
+    e: E,
+) -> Box<[A]> {
+    unsafe { init_slice::<E, L, A>(len, |_| e.clone()) }
+}
+
+/// Same as `boxarray_` but allocate a boxed slice of `len` nested arrays.
+///
+/// The function takes the coordinates inside a nested array followed by the index of that array in the slice, which is the
+/// same as the coordinates of a nested array with one more dimension of size `len`. If the function panics, the cells
+/// already initialized, in all the previous arrays, are dropped and the allocation is freed before the panic is propagated.
+///
+/// # Examples
+///
+/// ```
+/// let f = |((((), i), j), k)| (i + 3 * j + 192 * k) as f32;
+/// let frames: Box<[[[f32; 3]; 64]]> = boxarray::boxslice_(10, f);
+/// assert_eq!(frames[9][63][2], (2 + 3 * 63 + 192 * 9) as f32);
+///
+/// let fixed: Box<[[[f32; 3]; 64]; 10]> = boxarray::boxarray_(f);
+/// assert_eq!(*frames, *fixed);
+/// ```
+pub fn boxslice_<
+    E,
+    L: CUList + IndexCoord<L> + Product<usize>,
+    A: Arrays<E, L>,
+    F: Fn((CoordType<L>, usize)) -> E,
+>(
+    len: usize,
+    f: F,
+) -> Box<[A]> {
+    unsafe { init_slice::<E, L, A>(len, slabs_in_order::<E, L>(0, f)) }
+}
@@ -0,0 +1,102 @@
+//! Parallel initialization with the threads of the standard library.
+use crate::init;
+use crate::private::*;
+use std::thread;
+
+/// Initialize `n` values with `threads` scoped threads, or `available_parallelism` threads if `threads` is 0, each one
+/// initializing a contiguous slab of the outer-most array.
+pub(crate) unsafe fn threaded_init<E: Send, L: CUList + Product<usize> + Reify<CoordType<L>>, A>(
+    threads: usize,
+    fill_chunk: impl Fn(usize, *mut E, usize) + Sync,
+) -> Box<A> {
+    let threads = match threads {
+        0 => thread::available_parallelism().map_or(1, |n| n.get()),
+        n => n,
+    };
+    let mut dims = vec![0; <CoordType<L> as Coords>::RANK];
+    L::reify().write(&mut dims);
+    let outer = dims.last().copied().unwrap_or(1);
+    let n = L::product();
+    let slab = n.checked_div(outer).unwrap_or(0);
+    let chunk = slab * outer.div_ceil(threads);
+    init::init_chunks(
+        n,
+        chunk,
+        |count, run| {
+            thread::scope(|s| {
+                for c in 1..count {
+                    s.spawn(move || run(c));
+                }
+                if count > 0 {
+                    run(0);
+                }
+            })
+        },
+        fill_chunk,
+    )
+}
+
+/// Same as `boxarray` but initialize the cells with `threads` threads, or `std::thread::available_parallelism` threads if
+/// `threads` is 0.
+///
+/// See `boxarray_threaded_` for how the work is split.
+///
+/// # Examples
+///
+/// ```
+/// let a: Box<[[[f64; 100]; 100]; 100]> = boxarray::boxarray_threaded(0, 7.0);
+/// assert!(boxarray::as_flat::<f64, _, _>(&*a).iter().all(|x| *x == 7.0));
+/// ```
+pub fn boxarray_threaded<
+    E: Clone + Send + Sync,
+    L: CUList + Product<usize> + Reify<CoordType<L>>,
+    A: Arrays<E, L>,
+>(
+    threads: usize,
+    e: E,
+) -> Box<A> {
+    unsafe { threaded_init::<E, L, A>(threads, |_, ptr, len| init::fill(ptr, len, |_| e.clone())) }
+}
+
+/// Same as `boxarray_` but initialize the cells with `threads` threads, or `std::thread::available_parallelism` threads if
+/// `threads` is 0, using only `std::thread::scope`.
+///
+/// The outer-most array is split into contiguous slabs, one per thread, each thread initializing its own slab. As the memory
+/// of a slab is first written by the thread that initializes it, the operating system can place it close to that thread on
+/// NUMA systems. The function is called exactly once per cell, so the result is the same as with `boxarray_`. If the
+/// function panics, the cells already initialized are dropped and the allocation is freed before the panic is propagated.
+///
+/// # Examples
+///
+/// ```
+/// let f = |((((), i), j), k)| (i + 100 * j + 10_000 * k) as u64;
+/// let a: Box<[[[u64; 100]; 100]; 100]> = boxarray::boxarray_threaded_(4, f);
+/// let b: Box<[[[u64; 100]; 100]; 100]> = boxarray::boxarray_(f);
+/// assert_eq!(a, b);
+/// ```
+///
+/// Each thread initializes whole slabs of the outer-most array.
+/// ```
+/// use std::thread;
+///
+/// let a: Box<[[thread::ThreadId; 1000]; 8]> = boxarray::boxarray_threaded_(4, |_| thread::current().id());
+/// for slab in a.iter() {
+///     assert!(slab.iter().all(|id| *id == slab[0]));
+/// }
+/// assert_ne!(a[0][0], a[7][0]);
+/// ```
+pub fn boxarray_threaded_<
+    E: Send,
+    L: CUList + IndexCoord<L> + Product<usize> + Reify<CoordType<L>>,
+    A: Arrays<E, L>,
+    F: Fn(CoordType<L>) -> E + Sync,
+>(
+    threads: usize,
+    f: F,
+) -> Box<A> {
+    unsafe {
+        threaded_init::<E, L, A>(threads, |start, ptr, len| {
+            init::fill(ptr, len, in_order::<E, L>(start, &f))
+        })
+    }
+}
@@ -0,0 +1,224 @@
+//! Growable vector of nested arrays, each one initialized in place on the heap.
+use crate::init;
+use crate::private::*;
+use crate::slice::slabs_in_order;
+use std::fmt;
+use std::ops::{Deref, DerefMut};
+
+/// Growable vector of nested arrays `A` whose pushes write the cells directly into the spare capacity of the vector, so that
+/// large arrays such as `[[f32; 512]; 512]` are never built on the stack.
+///
+/// It dereferences to `[A]` and can be converted into a `Vec<A>` or a `Box<[A]>` without copy.
+///
+/// # Examples
+///
+/// ```
+/// use boxarray::BoxArrayVec;
+///
+/// let mut frames: BoxArrayVec<[[f32; 512]; 512]> = BoxArrayVec::new();
+/// frames.push_fill(0.0);
+/// frames.push_with(|(((), i), j)| (i + j) as f32);
+/// assert_eq!(frames.len(), 2);
+/// assert_eq!(frames[1][511][511], 1022.0);
+///
+/// let slice: &[[[f32; 512]; 512]] = &frames;
+/// assert_eq!(slice[0], [[0.0; 512]; 512]);
+/// ```
+pub struct BoxArrayVec<A> {
+    vec: Vec<A>,
+}
+
+impl<A> BoxArrayVec<A> {
+    /// Empty vector, which does not allocate.
+    pub fn new() -> Self {
+        BoxArrayVec { vec: Vec::new() }
+    }
+
+    /// Empty vector with room for at least `capacity` nested arrays.
+    pub fn with_capacity(capacity: usize) -> Self {
+        BoxArrayVec {
+            vec: Vec::with_capacity(capacity),
+        }
+    }
+
+    /// Number of nested arrays that the vector can hold without reallocating.
+    pub fn capacity(&self) -> usize {
+        self.vec.capacity()
+    }
+
+    /// Reserve room for at least `additional` more nested arrays.
+    pub fn reserve(&mut self, additional: usize) {
+        self.vec.reserve(additional)
+    }
+
+    /// The nested arrays as a slice.
+    pub fn as_slice(&self) -> &[A] {
+        &self.vec
+    }
+
+    /// The nested arrays as a mutable slice.
+    pub fn as_mut_slice(&mut self) -> &mut [A] {
+        &mut self.vec
+    }
+
+    /// Remove and return the last nested array.
+    pub fn pop(&mut self) -> Option<A> {
+        self.vec.pop()
+    }
+
+    /// Remove all the nested arrays, keeping the allocation.
+    pub fn clear(&mut self) {
+        self.vec.clear()
+    }
+
+    /// Convert into a `Vec`, reusing the allocation.
+    pub fn into_vec(self) -> Vec<A> {
+        self.vec
+    }
+
+    /// Convert into a boxed slice, which reallocates if the capacity is larger than the length.
+    pub fn into_boxed_slice(self) -> Box<[A]> {
+        self.vec.into_boxed_slice()
+    }
+
+    /// Append `count` nested arrays made of `L::product()` values of type `E` each, initialized in place with `f` through
+    /// the initialization core. If `f` panics, the cells already written are dropped and the length is left unchanged.
+    ///
+    /// # Safety
+    ///
+    /// `A` must consist of exactly `L::product()` consecutive values of type `E`.
+    unsafe fn append<E, L: CUList + Product<usize>>(
+        &mut self,
+        count: usize,
+        f: impl FnMut(usize) -> E,
+    ) {
+        let n = count.checked_mul(L::product()).expect("capacity overflow");
+        self.vec.reserve(count);
+        let len = self.vec.len();
+        init::fill(self.vec.as_mut_ptr().add(len) as *mut E, n, f);
+        self.vec.set_len(len + count);
+    }
+
+    /// Append a nested array with all its cells initialized to `e`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut v = boxarray::BoxArrayVec::<[[u8; 3]; 2]>::new();
+    /// v.push_fill(7);
+    /// assert_eq!(v.as_slice(), [[[7; 3]; 2]]);
+    /// ```
+    pub fn push_fill<E: Clone, L: CUList + Product<usize>>(&mut self, e: E)
+    where
+        A: Arrays<E, L>,
+    {
+        unsafe { self.append::<E, L>(1, |_| e.clone()) }
+    }
+
+    /// Append a nested array initialized with a function of the coordinates, as `boxarray_` does.
+    ///
+    /// If the function panics, the cells already initialized are dropped and the vector is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::panic::{catch_unwind, AssertUnwindSafe};
+    ///
+    /// let mut v = boxarray::BoxArrayVec::<[[String; 2]; 2]>::new();
+    /// v.push_with(|(((), i), j)| format!("{j}{i}"));
+    /// let res = catch_unwind(AssertUnwindSafe(|| {
+    ///     v.push_with(|(((), i), j)| if (i, j) == (1, 1) { panic!("cell ({i}, {j})") } else { String::new() });
+    /// }));
+    /// assert!(res.is_err());
+    /// assert_eq!(v.len(), 1);
+    /// assert_eq!(v[0], [["00", "01"], ["10", "11"]]);
+    /// ```
+    pub fn push_with<E, L: CUList + IndexCoord<L> + Product<usize>, F: Fn(CoordType<L>) -> E>(
+        &mut self,
+        f: F,
+    ) where
+        A: Arrays<E, L>,
+    {
+        unsafe { self.append::<E, L>(1, in_order::<E, L>(0, f)) }
+    }
+
+    /// Append `count` nested arrays initialized with a function of the coordinates followed by the index of the array in
+    /// the vector, as `boxslice_` does.
+    ///
+    /// If the function panics, the cells already initialized are dropped and none of the new arrays are kept.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// let mut v = boxarray::BoxArrayVec::<[[u32; 3]; 2]>::new();
+    /// v.push_fill(0);
+    /// v.extend_from_fn(2, |((((), i), j), k)| (i + 3 * j + 6 * k) as u32);
+    /// assert_eq!(v.len(), 3);
+    /// assert_eq!(v[1], [[6, 7, 8], [9, 10, 11]]);
+    /// assert_eq!(v[2][1][2], 17);
+    /// ```
+    pub fn extend_from_fn<
+        E,
+        L: CUList + IndexCoord<L> + Product<usize>,
+        F: Fn((CoordType<L>, usize)) -> E,
+    >(
+        &mut self,
+        count: usize,
+        f: F,
+    ) where
+        A: Arrays<E, L>,
+    {
+        let first = self.vec.len();
+        unsafe { self.append::<E, L>(count, slabs_in_order::<E, L>(first, f)) }
+    }
+}
+
+impl<A> Default for BoxArrayVec<A> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<A> Deref for BoxArrayVec<A> {
+    type Target = [A];
+
+    fn deref(&self) -> &[A] {
+        &self.vec
+    }
+}
+
+impl<A> DerefMut for BoxArrayVec<A> {
+    fn deref_mut(&mut self) -> &mut [A] {
+        &mut self.vec
+    }
+}
+
+impl<A> AsRef<[A]> for BoxArrayVec<A> {
+    fn as_ref(&self) -> &[A] {
+        &self.vec
+    }
+}
+
+impl<A> AsMut<[A]> for BoxArrayVec<A> {
+    fn as_mut(&mut self) -> &mut [A] {
+        &mut self.vec
+    }
+}
+
+impl<A> From<Vec<A>> for BoxArrayVec<A> {
+    fn from(vec: Vec<A>) -> Self {
+        BoxArrayVec { vec }
+    }
+}
+
+impl<A> From<BoxArrayVec<A>> for Vec<A> {
+    fn from(v: BoxArrayVec<A>) -> Self {
+        v.vec
+    }
+}
+
+impl<A: fmt::Debug> fmt::Debug for BoxArrayVec<A> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.vec.fmt(f)
+    }
+}
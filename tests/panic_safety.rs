@@ -0,0 +1,351 @@
+//! When the initialization of a cell panics, every constructor drops the cells already initialized exactly once and frees
+//! the allocation before propagating the panic. The same holds when the fallible constructors return an error.
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
+use boxarray::FromIterError;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicIsize, AtomicUsize, Ordering};
+use std::sync::Arc;
+
+/// Counter of the live `Counted` values of one test, which panics when creating the value of index `panic_at`.
+#[derive(Clone)]
+struct Counter(Arc<Counts>);
+
+struct Counts {
+    alive: AtomicIsize,
+    created: AtomicUsize,
+    panic_at: usize,
+}
+
+impl Counter {
+    fn new(panic_at: usize) -> Self {
+        Counter(Arc::new(Counts {
+            alive: AtomicIsize::new(0),
+            created: AtomicUsize::new(0),
+            panic_at,
+        }))
+    }
+
+    fn make(&self) -> Counted {
+        let n = self.0.created.fetch_add(1, Ordering::Relaxed);
+        if n == self.0.panic_at {
+            panic!("cannot create value {n}");
+        }
+        self.0.alive.fetch_add(1, Ordering::Relaxed);
+        Counted(self.clone())
+    }
+
+    fn alive(&self) -> isize {
+        self.0.alive.load(Ordering::Relaxed)
+    }
+}
+
+/// Value tracked by a `Counter`, whose clones are created through the counter.
+struct Counted(Counter);
+
+impl Clone for Counted {
+    fn clone(&self) -> Self {
+        self.0.make()
+    }
+}
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.0 .0.alive.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+type Grid = [[Counted; 8]; 4];
+
+/// Run `f` with a counter that panics when creating the value of index `panic_at`, and check that the panic is propagated
+/// with no value left alive.
+fn assert_panics_clean<R>(panic_at: usize, f: impl FnOnce(&Counter) -> R) {
+    let counter = Counter::new(panic_at);
+    let res = catch_unwind(AssertUnwindSafe(|| drop(f(&counter))));
+    assert!(res.is_err());
+    assert_eq!(counter.alive(), 0);
+}
+
+#[test]
+fn boxarray() {
+    assert_panics_clean(20, |c| boxarray::boxarray::<_, _, Grid>(c.make()));
+}
+
+#[test]
+fn try_boxarray() {
+    assert_panics_clean(20, |c| boxarray::try_boxarray::<_, _, Grid>(c.make()));
+}
+
+#[test]
+fn boxarray_() {
+    assert_panics_clean(20, |c| boxarray::boxarray_::<_, _, Grid, _>(|_| c.make()));
+}
+
+#[test]
+fn try_boxarray_() {
+    assert_panics_clean(20, |c| {
+        boxarray::try_boxarray_::<_, _, Grid, _>(|_| c.make())
+    });
+}
+
+#[test]
+fn try_boxarray_with() {
+    assert_panics_clean(20, |c| {
+        boxarray::try_boxarray_with::<_, (), _, Grid, _>(|_| Ok(c.make()))
+    });
+}
+
+#[test]
+fn boxarray_from_fn_mut() {
+    assert_panics_clean(20, |c| {
+        boxarray::boxarray_from_fn_mut::<_, _, Grid, _>(|_| c.make())
+    });
+}
+
+#[test]
+fn boxarray_from_iter() {
+    assert_panics_clean(20, |c| {
+        boxarray::boxarray_from_iter::<_, _, Grid, _>(std::iter::repeat_with(|| c.make()))
+    });
+}
+
+#[test]
+fn try_boxarray_with_error() {
+    let counter = Counter::new(usize::MAX);
+    let res = boxarray::try_boxarray_with::<_, _, _, Grid, _>(|(((), i), j)| {
+        if (i, j) == (3, 2) {
+            Err("cannot create cell")
+        } else {
+            Ok(counter.make())
+        }
+    });
+    let (c, err) = res.err().expect("cell (3, 2) fails").into_parts();
+    assert_eq!((c, err), ((((), 3), 2), "cannot create cell"));
+    assert_eq!(counter.alive(), 0);
+}
+
+#[test]
+fn boxarray_from_iter_too_few() {
+    let counter = Counter::new(usize::MAX);
+    let res = boxarray::boxarray_from_iter::<_, _, Grid, _>((0..10).map(|_| counter.make()));
+    assert!(matches!(res, Err(FromIterError::TooFew { missing: 22 })));
+    assert_eq!(counter.alive(), 0);
+}
+
+#[test]
+fn boxarray_from_iter_too_many() {
+    let counter = Counter::new(usize::MAX);
+    let res = boxarray::boxarray_from_iter::<_, _, Grid, _>((0..40).map(|_| counter.make()));
+    assert!(matches!(res, Err(FromIterError::TooMany)));
+    assert_eq!(counter.alive(), 0);
+}
+
+#[test]
+fn boxarray_idx() {
+    assert_panics_clean(20, |c| {
+        boxarray::boxarray_idx::<_, _, Grid, _, 2>(|_| c.make())
+    });
+}
+
+#[test]
+fn try_boxarray_idx() {
+    assert_panics_clean(20, |c| {
+        boxarray::try_boxarray_idx::<_, _, Grid, _, 2>(|_| c.make())
+    });
+}
+
+#[test]
+fn try_boxarray_idx_with() {
+    assert_panics_clean(20, |c| {
+        boxarray::try_boxarray_idx_with::<_, (), _, Grid, _, 2>(|_| Ok(c.make()))
+    });
+}
+
+#[test]
+fn boxarray_idx_from_fn_mut() {
+    assert_panics_clean(20, |c| {
+        boxarray::boxarray_idx_from_fn_mut::<_, _, Grid, _, 2>(|_| c.make())
+    });
+}
+
+#[test]
+fn boxarray_outer_first() {
+    assert_panics_clean(20, |c| {
+        boxarray::boxarray_outer_first::<_, _, Grid, _, 2>(|_| c.make())
+    });
+}
+
+#[test]
+fn rank() {
+    type Rank = boxarray::Rank<2>;
+    assert_panics_clean(20, |c| Rank::boxarray::<Grid>(c.make()));
+    assert_panics_clean(20, |c| Rank::boxarray_::<Grid, _>(|_| c.make()));
+    assert_panics_clean(20, |c| Rank::boxarray_idx::<Grid, _>(|_| c.make()));
+    assert_panics_clean(20, |c| Rank::try_boxarray_::<Grid, _>(|_| c.make()));
+    assert_panics_clean(20, |c| {
+        Rank::try_boxarray_with::<(), Grid, _>(|_| Ok(c.make()))
+    });
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_boxarray() {
+    assert_panics_clean(50_000, |c| {
+        boxarray::par_boxarray::<_, _, [[Counted; 1000]; 100]>(c.make())
+    });
+}
+
+#[cfg(feature = "rayon")]
+#[test]
+fn par_boxarray_() {
+    assert_panics_clean(50_000, |c| {
+        boxarray::par_boxarray_::<_, _, [[Counted; 1000]; 100], _>(|_| c.make())
+    });
+}
+
+#[test]
+fn boxarray_threaded() {
+    assert_panics_clean(5000, |c| {
+        boxarray::boxarray_threaded::<_, _, [[Counted; 1000]; 8]>(4, c.make())
+    });
+}
+
+#[test]
+fn boxarray_threaded_() {
+    assert_panics_clean(5000, |c| {
+        boxarray::boxarray_threaded_::<_, _, [[Counted; 1000]; 8], _>(4, |_| c.make())
+    });
+}
+
+#[test]
+fn boxarray_aligned() {
+    assert_panics_clean(20, |c| {
+        boxarray::boxarray_aligned::<64, _, _, Grid>(c.make())
+    });
+}
+
+#[test]
+fn boxarray_aligned_() {
+    assert_panics_clean(20, |c| {
+        boxarray::boxarray_aligned_::<64, _, _, Grid, _>(|_| c.make())
+    });
+}
+
+#[test]
+fn rcarray() {
+    assert_panics_clean(20, |c| boxarray::rcarray::<_, _, Grid>(c.make()));
+}
+
+#[test]
+fn rcarray_() {
+    assert_panics_clean(20, |c| boxarray::rcarray_::<_, _, Grid, _>(|_| c.make()));
+}
+
+#[test]
+fn arcarray() {
+    assert_panics_clean(20, |c| boxarray::arcarray::<_, _, Grid>(c.make()));
+}
+
+#[test]
+fn arcarray_() {
+    assert_panics_clean(20, |c| boxarray::arcarray_::<_, _, Grid, _>(|_| c.make()));
+}
+
+#[test]
+fn boxslice() {
+    assert_panics_clean(70, |c| boxarray::boxslice::<_, _, Grid>(5, c.make()));
+}
+
+#[test]
+fn boxslice_() {
+    assert_panics_clean(70, |c| {
+        boxarray::boxslice_::<_, _, Grid, _>(5, |_| c.make())
+    });
+}
+
+/// Push one array into a `BoxArrayVec`, then run `push` with a counter that panics when creating the value of index
+/// `panic_at`, and check that the panic is propagated with only the first array left in the vector and alive.
+fn assert_push_panics_clean(
+    panic_at: usize,
+    push: impl FnOnce(&mut boxarray::BoxArrayVec<Grid>, &Counter),
+) {
+    let counter = Counter::new(panic_at);
+    let mut v = boxarray::BoxArrayVec::<Grid>::new();
+    v.push_with(|_| counter.make());
+    let res = catch_unwind(AssertUnwindSafe(|| push(&mut v, &counter)));
+    assert!(res.is_err());
+    assert_eq!(v.len(), 1);
+    assert_eq!(counter.alive(), 32);
+    drop(v);
+    assert_eq!(counter.alive(), 0);
+}
+
+#[test]
+fn box_array_vec_push_fill() {
+    assert_push_panics_clean(50, |v, c| v.push_fill(c.make()));
+}
+
+#[test]
+fn box_array_vec_push_with() {
+    assert_push_panics_clean(50, |v, c| v.push_with(|_| c.make()));
+}
+
+#[test]
+fn box_array_vec_extend_from_fn() {
+    assert_push_panics_clean(80, |v, c| v.extend_from_fn(3, |_| c.make()));
+}
+
+#[cfg(feature = "rand")]
+mod random {
+    use super::*;
+    use boxarray::rand_distr::{Distribution, StandardUniform};
+
+    /// Distribution of `Counted` values created through the counter.
+    fn counted(c: &Counter) -> impl Distribution<Counted> + Sync + '_ {
+        StandardUniform.map(|_: u8| c.make())
+    }
+
+    #[test]
+    fn boxarray_random() {
+        assert_panics_clean(5000, |c| {
+            boxarray::boxarray_random::<_, _, [[Counted; 1000]; 8], _>(1, counted(c))
+        });
+    }
+
+    #[test]
+    fn boxarray_random_threaded() {
+        assert_panics_clean(5000, |c| {
+            boxarray::boxarray_random_threaded::<_, _, [[Counted; 1000]; 8], _>(4, 1, counted(c))
+        });
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn par_boxarray_random() {
+        assert_panics_clean(50_000, |c| {
+            boxarray::par_boxarray_random::<_, _, [[Counted; 1000]; 100], _>(1, counted(c))
+        });
+    }
+}
+
+#[cfg(any(feature = "allocator_api", feature = "allocator-api2"))]
+mod allocator {
+    use super::*;
+    #[cfg(not(feature = "allocator_api"))]
+    use boxarray::allocator_api2::alloc::Global;
+    #[cfg(feature = "allocator_api")]
+    use std::alloc::Global;
+
+    #[test]
+    fn boxarray_in() {
+        assert_panics_clean(20, |c| {
+            boxarray::boxarray_in::<_, _, Grid, _>(c.make(), Global)
+        });
+    }
+
+    #[test]
+    fn boxarray_in_() {
+        assert_panics_clean(20, |c| {
+            boxarray::boxarray_in_::<_, _, Grid, _, _>(|_| c.make(), Global)
+        });
+    }
+}